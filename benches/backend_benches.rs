@@ -0,0 +1,116 @@
+use cheminee_similarity_model::config::{AssignmentBackend, EncoderConfig};
+use cheminee_similarity_model::encoder::{build_encoder_model_with_config, EncoderModel};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ndarray::{s, Array2};
+
+const BATCH_SIZES: [usize; 3] = [1, 64, 1024];
+const FINGERPRINT_BITS: usize = 2048;
+// Roughly the density of the bundled model's training fingerprints
+const BITS_SET: usize = 48;
+const TOP_N: usize = 5;
+
+// Deterministic sparse fingerprints from an xorshift stream, so runs compare like for like
+fn fingerprints(rows: usize) -> Vec<Vec<i64>> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..rows)
+        .map(|_| {
+            let mut row = vec![0; FINGERPRINT_BITS];
+            for _ in 0..BITS_SET {
+                row[next() as usize % FINGERPRINT_BITS] = 1;
+            }
+            row
+        })
+        .collect()
+}
+
+fn model(assignment_backend: AssignmentBackend) -> EncoderModel {
+    let config = EncoderConfig {
+        assignment_backend,
+        ..EncoderConfig::default()
+    };
+
+    let encoder_model = build_encoder_model_with_config(config).unwrap();
+    encoder_model.warm_up().unwrap();
+    encoder_model
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let encoder_model = model(AssignmentBackend::default());
+    let mut group = c.benchmark_group("encode");
+
+    for batch_size in BATCH_SIZES {
+        let input_data = fingerprints(batch_size);
+        group.throughput(Throughput::Elements(batch_size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(batch_size), &input_data, |b, input_data| {
+            b.iter(|| encoder_model.encode_latent(input_data).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+// Assignment alone, over latents encoded up front
+fn bench_assign(c: &mut Criterion) {
+    let tf_model = model(AssignmentBackend::TensorFlow);
+    let native_model = model(AssignmentBackend::Native);
+    let mut group = c.benchmark_group("assign");
+
+    for batch_size in BATCH_SIZES {
+        let latents = tf_model.encode_latent(&fingerprints(batch_size)).unwrap();
+        let rows = (0..batch_size)
+            .map(|row| latents.slice(s![row..row + 1, ..]).to_owned())
+            .collect::<Vec<Array2<f32>>>();
+        group.throughput(Throughput::Elements(batch_size as u64));
+
+        group.bench_with_input(BenchmarkId::new("tf_per_row", batch_size), &rows, |b, rows| {
+            b.iter(|| {
+                for row in rows {
+                    tf_model.assign_latents(row, Some(TOP_N)).unwrap();
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("tf_batched", batch_size), &latents, |b, latents| {
+            b.iter(|| tf_model.assign_latents(latents, Some(TOP_N)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("native", batch_size), &latents, |b, latents| {
+            b.iter(|| native_model.assign_latents(latents, Some(TOP_N)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+// Encode plus assignment, as callers of `transform_top_n` see it
+fn bench_transform(c: &mut Criterion) {
+    let tf_model = model(AssignmentBackend::TensorFlow);
+    let native_model = model(AssignmentBackend::Native);
+    let mut group = c.benchmark_group("transform");
+
+    for batch_size in BATCH_SIZES {
+        let input_data = fingerprints(batch_size);
+        group.throughput(Throughput::Elements(batch_size as u64));
+
+        group.bench_with_input(BenchmarkId::new("tf", batch_size), &input_data, |b, input_data| {
+            b.iter(|| tf_model.transform_top_n(input_data, TOP_N).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("native", batch_size), &input_data, |b, input_data| {
+            b.iter(|| native_model.transform_top_n(input_data, TOP_N).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = bench_encode, bench_assign, bench_transform
+}
+criterion_main!(benches);
@@ -4,13 +4,51 @@ use std::io::BufReader;
 use tar::Archive;
 
 fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/cheminee_similarity.proto").expect("Failed to compile protos");
+
+    // The pure-Rust build has no model to bundle
+    if std::env::var("CARGO_FEATURE_MODEL").is_err() {
+        return;
+    }
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is not set");
+    let embedded_centroids = std::env::var("CARGO_FEATURE_EMBEDDED_CENTROIDS").is_ok();
+
+    // Build scripts run from the package root, so a relative path resolves against the manifest dir
+    // rather than against src/, where `include_bytes!` would look for it
+    println!("cargo:rerun-if-env-changed=CHEMINEE_EMBEDDED_CENTROIDS");
+    let custom_centroids = std::env::var("CHEMINEE_EMBEDDED_CENTROIDS")
+        .ok()
+        .filter(|_| embedded_centroids)
+        .map(|path| {
+            std::fs::canonicalize(&path).unwrap_or_else(|e| panic!("Failed to resolve CHEMINEE_EMBEDDED_CENTROIDS {}: {}", path, e))
+        });
+
+    // A custom embedded centroid file replaces the bundled assets, so there is nothing to download
+    if custom_centroids.is_none() {
+        download_assets(&out_dir);
+    }
+
+    if embedded_centroids {
+        let centroids_path = match custom_centroids {
+            Some(path) => {
+                println!("cargo:rerun-if-changed={}", path.display());
+                path.display().to_string()
+            }
+            None => format!("{}/assets/lf_kmeans_10k_centroids_20241111.csv", out_dir),
+        };
+        println!("cargo:rustc-env=CHEMINEE_EMBEDDED_CENTROIDS_PATH={}", centroids_path);
+    }
+}
+
+fn download_assets(out_dir: &str) {
     let asset_bytes = reqwest::blocking::get("https://cheminee-models.s3.eu-central-1.amazonaws.com/similarity/similarity-0.1.0.tar.gz")
         .expect("Failed get request")
         .bytes()
         .expect("Failed to retrieve bytes");
 
-    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is not set");
-    let tar_gz_path = format!("{}/similarity-0.1.0.tar.gz", &out_dir);
+    let tar_gz_path = format!("{}/similarity-0.1.0.tar.gz", out_dir);
 
     std::fs::write(&tar_gz_path, asset_bytes).expect("Failed to write tar file");
 
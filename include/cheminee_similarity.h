@@ -0,0 +1,71 @@
+/* Generated by cbindgen from src/ffi.rs; regenerate with
+ * `cbindgen --config cbindgen.toml --output include/cheminee_similarity.h` */
+
+#ifndef CHEMINEE_SIMILARITY_H
+#define CHEMINEE_SIMILARITY_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Label written for ranks a row has no cluster for, e.g. rows skipped by the NaN policy
+ */
+#define CSW_NO_LABEL -1
+
+/**
+ * Opaque handle to a loaded model
+ */
+typedef struct CswModel CswModel;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Loads a model from an assets directory, or the default assets when `assets_path` is null.
+ * Returns null on failure. Free the model with `csw_free`.
+ *
+ * # Safety
+ * `assets_path` must be null or a valid NUL-terminated string.
+ */
+CswModel *csw_model_load(const char *assets_path);
+
+/**
+ * Assigns the nearest `top_n` clusters to `num_rows` row-major fingerprints of `num_bits` each,
+ * writing `num_rows * top_n` labels to `labels_out`, nearest first. Missing ranks are filled
+ * with `CSW_NO_LABEL`. Returns 0 on success, and nonzero on failure, including buffer sizes
+ * that overflow `size_t`.
+ *
+ * # Safety
+ * `model` must come from `csw_model_load`, `fingerprints` must hold `num_rows * num_bits`
+ * values and `labels_out` must have room for `num_rows * top_n` labels.
+ */
+int32_t csw_transform(const CswModel *model,
+                      const int64_t *fingerprints,
+                      size_t num_rows,
+                      size_t num_bits,
+                      size_t top_n,
+                      int32_t *labels_out);
+
+/**
+ * Frees a model from `csw_model_load`; null is ignored.
+ *
+ * # Safety
+ * `model` must be null or come from `csw_model_load`, and must not be used afterwards.
+ */
+void csw_free(CswModel *model);
+
+/**
+ * Message of the last failure on the calling thread, or null. Valid until the next call on
+ * this thread; do not free it.
+ */
+const char *csw_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHEMINEE_SIMILARITY_H */
@@ -0,0 +1,144 @@
+use crate::centroids::{row_major, Centroids};
+use crate::metric::DistanceMetric;
+use hnsw_rs::prelude::*;
+
+const MAX_LAYERS: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct HnswConfig {
+    pub max_connections: usize,
+    pub ef_construction: usize,
+    /// Candidate list size at query time; higher is slower but closer to exact
+    pub ef_search: usize,
+    /// When non-zero, `transform_approximate` also ranks up to this many rows of each call exactly
+    /// and logs the index's recall against them
+    pub exactness_check_rows: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        HnswConfig {
+            max_connections: 16,
+            ef_construction: 200,
+            ef_search: 64,
+            exactness_check_rows: 0,
+        }
+    }
+}
+
+/// Approximate nearest-centroid index; results may miss the true nearest clusters.
+pub struct CentroidIndex {
+    hnsw: MetricHnsw,
+    metric: DistanceMetric,
+    dim: usize,
+    ef_search: usize,
+}
+
+// Cosine searches L2 over unit-length vectors, which ranks neighbours the same way
+enum MetricHnsw {
+    L2(Hnsw<'static, f32, DistL2>),
+    L1(Hnsw<'static, f32, DistL1>),
+}
+
+impl CentroidIndex {
+    pub fn build(centroids: &Centroids, config: &HnswConfig, metric: DistanceMetric) -> Self {
+        Self::build_from_rows(&row_major(&centroids.coordinates), centroids.dim(), config, metric)
+    }
+
+    /// Indexes any row-major `[rows, dim]` matrix; search labels are row indices.
+    pub fn build_from_rows(coordinates: &[f32], dim: usize, config: &HnswConfig, metric: DistanceMetric) -> Self {
+        let num_clusters = coordinates.len() / dim;
+
+        let rows = coordinates
+            .chunks(dim)
+            .map(|row| match metric {
+                DistanceMetric::Cosine => unit_length(row),
+                _ => row.to_vec(),
+            })
+            .collect::<Vec<Vec<f32>>>();
+        let rows = rows
+            .iter()
+            .enumerate()
+            .map(|(cluster_id, row)| (row.as_slice(), cluster_id))
+            .collect::<Vec<(&[f32], usize)>>();
+
+        let hnsw = match metric {
+            DistanceMetric::Euclidean | DistanceMetric::Cosine | DistanceMetric::Mahalanobis => {
+                let hnsw = Hnsw::new(config.max_connections, num_clusters, MAX_LAYERS, config.ef_construction, DistL2 {});
+                hnsw.parallel_insert_slice(&rows);
+                MetricHnsw::L2(hnsw)
+            }
+            DistanceMetric::Manhattan => {
+                let hnsw = Hnsw::new(config.max_connections, num_clusters, MAX_LAYERS, config.ef_construction, DistL1 {});
+                hnsw.parallel_insert_slice(&rows);
+                MetricHnsw::L1(hnsw)
+            }
+        };
+
+        CentroidIndex {
+            hnsw,
+            metric,
+            dim,
+            ef_search: config.ef_search,
+        }
+    }
+
+    /// Returns up to `k` `(label, distance)` pairs, with distances on the same scale as exact
+    /// assignment under the metric the index was built for.
+    pub fn search(&self, latent: &[f32], k: usize) -> Vec<(i32, f32)> {
+        let ef_search = self.ef_search.max(k);
+        let neighbours = match (&self.hnsw, self.metric) {
+            (MetricHnsw::L2(hnsw), DistanceMetric::Cosine) => hnsw.search(&unit_length(latent), k, ef_search),
+            (MetricHnsw::L2(hnsw), _) => hnsw.search(latent, k, ef_search),
+            (MetricHnsw::L1(hnsw), _) => hnsw.search(latent, k, ef_search),
+        };
+
+        neighbours
+            .into_iter()
+            .map(|neighbour| (neighbour.d_id as i32, self.rescale(neighbour.distance)))
+            .collect()
+    }
+
+    // Converts HNSW's raw distance to the metric's own scale
+    fn rescale(&self, distance: f32) -> f32 {
+        match self.metric {
+            DistanceMetric::Euclidean | DistanceMetric::Mahalanobis => distance / (self.dim as f32).sqrt(),
+            // Between unit vectors `|x - c|^2 = 2 - 2 cos(x, c)`
+            DistanceMetric::Cosine => distance * distance / 2.0,
+            DistanceMetric::Manhattan => distance / self.dim as f32,
+        }
+    }
+}
+
+/// Fraction of the exact top-k labels that the approximate search also returned, averaged over
+/// rows. Rows the approximate search left empty (skipped NaN rows) are not counted; with no rows
+/// to compare the recall is 1.
+pub fn top_k_recall(approximate: &[Vec<(i32, f32)>], exact: &[Vec<(i32, f32)>]) -> f32 {
+    let row_recalls = approximate
+        .iter()
+        .zip(exact)
+        .filter(|(approximate_row, exact_row)| !approximate_row.is_empty() && !exact_row.is_empty())
+        .map(|(approximate_row, exact_row)| {
+            let found = exact_row
+                .iter()
+                .filter(|(label, _)| approximate_row.iter().any(|(approximate_label, _)| approximate_label == label))
+                .count();
+            found as f32 / exact_row.len() as f32
+        })
+        .collect::<Vec<f32>>();
+
+    if row_recalls.is_empty() {
+        return 1.0;
+    }
+
+    row_recalls.iter().sum::<f32>() / row_recalls.len() as f32
+}
+
+fn unit_length(row: &[f32]) -> Vec<f32> {
+    let norm = row.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return row.to_vec();
+    }
+
+    row.iter().map(|value| value / norm).collect()
+}
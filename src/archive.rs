@@ -0,0 +1,46 @@
+use crate::error::{EncoderError, Result};
+use flate2::bufread::GzDecoder;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+pub fn extract_archive(archive_path: &Path, dest: &Path) -> Result<()> {
+    let file_name = archive_path.to_string_lossy().to_lowercase();
+    let archive_file = File::open(archive_path)?;
+
+    if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        let decoder = GzDecoder::new(BufReader::new(archive_file));
+        Archive::new(decoder).unpack(dest)?;
+    } else if file_name.ends_with(".tar") {
+        Archive::new(BufReader::new(archive_file)).unpack(dest)?;
+    } else if file_name.ends_with(".zip") {
+        zip::ZipArchive::new(archive_file)?.extract(dest)?;
+    } else {
+        return Err(EncoderError::InvalidArgument(format!(
+            "Unsupported archive format for {}; expected .tar.gz, .tgz, .tar or .zip",
+            archive_path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+// Archives are either packed from inside the assets dir or wrap it in a single top-level folder
+pub fn find_assets_root(extract_dir: &Path, encoder_dir: &str) -> Result<PathBuf> {
+    if extract_dir.join(encoder_dir).is_dir() {
+        return Ok(extract_dir.to_path_buf());
+    }
+
+    for entry in std::fs::read_dir(extract_dir)? {
+        let path = entry?.path();
+        if path.is_dir() && path.join(encoder_dir).is_dir() {
+            return Ok(path);
+        }
+    }
+
+    Err(EncoderError::MissingAssets(format!(
+        "Archive does not contain a {} directory at its root or one level down",
+        encoder_dir
+    )))
+}
@@ -0,0 +1,251 @@
+use crate::centroids::row_major;
+#[cfg(feature = "tensorflow")]
+use crate::config::{ApproxTopK, SessionConfig};
+#[cfg(feature = "tensorflow")]
+use crate::error::{EncoderError, Result};
+use crate::metric::DistanceMetric;
+#[cfg(feature = "tensorflow")]
+use crate::session::assignment_session_options;
+use ndarray::Array2;
+#[cfg(feature = "tensorflow")]
+use tensorflow::{ops, DataType, Operation, Output, Scope, Session, SessionRunArgs, Tensor};
+
+/// A distance and top-k graph with the centroids frozen in as constants. Built once, then fed
+/// `[batch, dim]` latents on every run.
+#[cfg(feature = "tensorflow")]
+pub(crate) struct AssignmentGraph {
+    session: Session,
+    lf_input: Operation,
+    // Scalar `k` fed per run for `TopKV2`; `ApproxTopK` bakes `k` into the op instead
+    k_input: Option<Operation>,
+    top_k: Operation,
+}
+
+#[cfg(feature = "tensorflow")]
+impl AssignmentGraph {
+    /// Ranks the nearest clusters exactly with `TopKV2`, taking `k` per run, or the nearest
+    /// `approx_k` approximately with `ApproxTopK` when a recall target is given.
+    pub(crate) fn build<F>(
+        centroids: &Array2<f32>,
+        distance_fn: &F,
+        approx_k: usize,
+        recall_target: Option<f32>,
+        session_config: &SessionConfig,
+    ) -> Result<Self>
+    where
+        F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+    {
+        let mut scope = Scope::new_root_scope();
+
+        let centroids_const = ops::Const::new()
+            .dtype(DataType::Float)
+            .value(to_tensor(centroids)?)
+            .build(&mut scope)?;
+
+        let latent_shape = [-1, centroids.ncols() as i64];
+        let lf_input = ops::Placeholder::new()
+            .dtype(DataType::Float)
+            .shape(&latent_shape[..])
+            .build(&mut scope)?;
+
+        let distance = distance_fn(&mut scope, centroids_const.into(), lf_input.clone().into())?;
+
+        let negated_distance = ops::Neg::new()
+            .build(distance, &mut scope)?;
+
+        let (k_input, top_k) = match recall_target {
+            Some(recall_target) => {
+                let top_k = approx_top_k_op(&mut scope, negated_distance.into(), approx_k, recall_target)?;
+                (None, top_k)
+            }
+            None => {
+                let k_input = ops::Placeholder::new()
+                    .dtype(DataType::Int32)
+                    .shape(&[] as &[i64])
+                    .build(&mut scope)?;
+
+                let top_k = ops::TopKV2::new()
+                    .build(negated_distance, k_input.clone(), &mut scope)?;
+                (Some(k_input), top_k)
+            }
+        };
+
+        let session = Session::new(&assignment_session_options(session_config)?, &scope.graph())?;
+
+        Ok(AssignmentGraph {
+            session,
+            lf_input,
+            k_input,
+            top_k,
+        })
+    }
+
+    /// The nearest `k` `(centroid_index, distance)` pairs for every latent row; `k` only applies to
+    /// exact graphs.
+    pub(crate) fn run(&self, lf_array: &Array2<f32>, k: usize) -> Result<Vec<Vec<(i32, f32)>>> {
+        let lf_tensor = to_tensor(lf_array)?;
+        let k_tensor = Tensor::from(k as i32);
+
+        let mut run_args = SessionRunArgs::new();
+        run_args.add_feed(&self.lf_input, 0, &lf_tensor);
+        if let Some(k_input) = &self.k_input {
+            run_args.add_feed(k_input, 0, &k_tensor);
+        }
+
+        let distances_token = run_args.request_fetch(&self.top_k, 0);
+        let top_k_token = run_args.request_fetch(&self.top_k, 1);
+        self.session.run(&mut run_args)?;
+
+        let negated_distances: Tensor<f32> = run_args.fetch(distances_token)?;
+        let ranked_cluster_labels: Tensor<i32> = run_args.fetch(top_k_token)?;
+        let k = ranked_cluster_labels.dims()[1] as usize;
+
+        let ranked_clusters = ranked_cluster_labels
+            .chunks(k)
+            .zip(negated_distances.chunks(k))
+            .map(|(labels, negated_distances)| {
+                labels
+                    .iter()
+                    .zip(negated_distances)
+                    .map(|(&label, &negated_distance)| (label, -negated_distance))
+                    .collect()
+            })
+            .collect();
+
+        Ok(ranked_clusters)
+    }
+}
+
+/// The exact graph for a centroid set, plus an `ApproxTopK` one when configured and the linked
+/// TensorFlow registers the op. Without it the exact graph ranks the same `k` instead.
+#[cfg(feature = "tensorflow")]
+pub(crate) struct AssignmentGraphs {
+    exact: AssignmentGraph,
+    // `None` when not configured, or when `ApproxTopK` turned out unavailable at build time
+    approximate: Option<AssignmentGraph>,
+    num_clusters: usize,
+    // Clusters ranked when no top-n is requested: all of them, or the `ApproxTopK` k
+    default_k: usize,
+}
+
+#[cfg(feature = "tensorflow")]
+impl AssignmentGraphs {
+    pub(crate) fn build<F>(
+        centroids: &Array2<f32>,
+        distance_fn: &F,
+        approx_top_k: Option<ApproxTopK>,
+        session: &SessionConfig,
+    ) -> Result<Self>
+    where
+        F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+    {
+        let num_clusters = centroids.nrows();
+
+        let exact = AssignmentGraph::build(centroids, distance_fn, num_clusters, None, session)?;
+
+        let Some(approx_top_k) = approx_top_k else {
+            return Ok(AssignmentGraphs {
+                exact,
+                approximate: None,
+                num_clusters,
+                default_k: num_clusters,
+            });
+        };
+
+        let k = approx_top_k.k.clamp(1, num_clusters);
+
+        let approximate = match build_approximate(centroids, distance_fn, k, approx_top_k.recall_target, session) {
+            Ok(approximate) => Some(approximate),
+            Err(e) if approx_top_k_unregistered(&e) => {
+                tracing::info!("ApproxTopK is unavailable, falling back to TopKV2: {e}");
+                None
+            },
+            Err(e) => return Err(e),
+        };
+
+        Ok(AssignmentGraphs {
+            exact,
+            approximate,
+            num_clusters,
+            default_k: k,
+        })
+    }
+
+    /// Ranks the nearest `top_n` clusters per row, or the default number when `None`. The
+    /// approximate graph is only used when `top_n` fits within its `k`.
+    pub(crate) fn rank(&self, lf_array: &Array2<f32>, top_n: Option<usize>) -> Result<Vec<Vec<(i32, f32)>>> {
+        let k = top_n.unwrap_or(self.default_k).clamp(1, self.num_clusters);
+
+        if let Some(approximate) = self.approximate.as_ref().filter(|_| k <= self.default_k) {
+            let mut ranked_clusters = approximate.run(lf_array, k)?;
+            ranked_clusters.iter_mut().for_each(|row| row.truncate(k));
+            return Ok(ranked_clusters);
+        }
+
+        self.exact.run(lf_array, k)
+    }
+}
+
+/// Exact ranking of the nearest `top_n` centroids (all when `None`) for every latent row in plain
+/// Rust, bypassing TensorFlow. Rows are ranked in parallel with the `rayon` feature.
+pub(crate) fn rank_native(
+    lf_array: &Array2<f32>,
+    centroids: &Array2<f32>,
+    metric: DistanceMetric,
+    top_n: Option<usize>,
+) -> Vec<Vec<(i32, f32)>> {
+    metric.rank(&row_major(lf_array), &row_major(centroids), centroids.ncols(), top_n)
+}
+
+/// Copies a `[rows, cols]` array into a TF tensor of the same shape.
+#[cfg(feature = "tensorflow")]
+pub(crate) fn to_tensor(array: &Array2<f32>) -> Result<Tensor<f32>> {
+    let tensor = Tensor::new(&[array.nrows() as u64, array.ncols() as u64]).with_values(&row_major(array))?;
+    Ok(tensor)
+}
+
+// Builds the `ApproxTopK` graph and runs it once on a zero row, so a TF build that has the op but
+// no kernel for this device is caught here rather than on the first real batch
+#[cfg(feature = "tensorflow")]
+fn build_approximate<F>(
+    centroids: &Array2<f32>,
+    distance_fn: &F,
+    k: usize,
+    recall_target: f32,
+    session: &SessionConfig,
+) -> Result<AssignmentGraph>
+where
+    F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+{
+    let approximate = AssignmentGraph::build(centroids, distance_fn, k, Some(recall_target), session)?;
+    approximate.run(&Array2::zeros((1, centroids.ncols())), k)?;
+    Ok(approximate)
+}
+
+// Whether `e` is TF reporting no `ApproxTopK` op or kernel, the one failure worth falling back on
+#[cfg(feature = "tensorflow")]
+fn approx_top_k_unregistered(e: &EncoderError) -> bool {
+    match e {
+        EncoderError::TensorFlow(status) => {
+            let message = status.to_string();
+            message.contains("ApproxTopK") && message.contains("registered")
+        },
+        _ => false,
+    }
+}
+
+// `ApproxTopK` has no generated op wrapper, so it is built from the raw op definition
+#[cfg(feature = "tensorflow")]
+fn approx_top_k_op(scope: &mut Scope, input: Output, k: usize, recall_target: f32) -> Result<Operation> {
+    let op_name = scope.get_unique_name_for_op("ApproxTopK");
+    let mut graph = scope.graph_mut();
+
+    let mut op_description = graph.new_operation("ApproxTopK", &op_name)?;
+    op_description.add_input(input);
+    op_description.set_attr_type("T", DataType::Float)?;
+    op_description.set_attr_int("k", k as i64)?;
+    op_description.set_attr_float("recall_target", recall_target)?;
+    op_description.set_attr_bool("is_max_k", true)?;
+
+    Ok(op_description.finish()?)
+}
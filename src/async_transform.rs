@@ -0,0 +1,19 @@
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use std::sync::Arc;
+
+impl EncoderModel {
+    /// `transform` on tokio's blocking thread pool, so inference never runs on a runtime worker.
+    /// Takes owned input and a shared model because the blocking task may outlive the caller's
+    /// borrow if the future is dropped.
+    pub async fn transform_async(self: Arc<Self>, input_data: Vec<Vec<i64>>) -> Result<Vec<Vec<i32>>> {
+        let total = input_data.len();
+
+        match tokio::task::spawn_blocking(move || self.transform(&input_data)).await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            // Only happens when the runtime shuts down before the task starts
+            Err(_) => Err(EncoderError::Cancelled { assigned: 0, total }),
+        }
+    }
+}
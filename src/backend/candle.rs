@@ -0,0 +1,129 @@
+use crate::error::{EncoderError, Result};
+use super::EncoderInput;
+use candle_core::{DType, Device, Module};
+use candle_nn::Linear;
+use ndarray::Array2;
+use std::path::Path;
+
+/// The VAE encoder's dense stack, loaded from safetensors and run on CUDA or Metal when candle
+/// was built with that device and one is present, falling back to the CPU otherwise.
+///
+/// Layers are read as `dense_0.weight`/`dense_0.bias`, `dense_1.weight`, ... with weights shaped
+/// `[out, in]` and optional biases shaped `[out]`; every layer but the last (the latent mean) is
+/// followed by a ReLU. Loading fails on mismatched shapes or any other tensor in the file.
+pub(crate) struct CandleEncoder {
+    device: Device,
+    layers: Vec<Linear>,
+}
+
+impl CandleEncoder {
+    pub(crate) fn input_dim(&self) -> Option<usize> {
+        self.layers[0].weight().dims2().ok().map(|(_, input_dim)| input_dim)
+    }
+
+    pub(crate) fn latent_dim(&self) -> Option<usize> {
+        self.layers.last()?.weight().dims2().ok().map(|(latent_dim, _)| latent_dim)
+    }
+
+    pub(crate) fn load(model_path: &Path) -> Result<Self> {
+        let device = select_device()?;
+        let mut weights = candle_core::safetensors::load(model_path, &device)?;
+
+        let mut layers: Vec<Linear> = Vec::new();
+        while let Some(weight) = weights.remove(&format!("dense_{}.weight", layers.len())) {
+            let layer_idx = layers.len();
+            let (out_dim, in_dim) = weight
+                .dims2()
+                .map_err(|_| EncoderError::Model(format!("dense_{}.weight must be [out, in], got {:?}", layer_idx, weight.dims())))?;
+
+            let previous_out_dim = layers.last().and_then(|layer| layer.weight().dims2().ok()).map(|(out_dim, _)| out_dim);
+            if let Some(previous_out_dim) = previous_out_dim.filter(|&dim| dim != in_dim) {
+                return Err(EncoderError::Model(format!(
+                    "dense_{}.weight takes {} inputs, but dense_{} produces {}",
+                    layer_idx,
+                    in_dim,
+                    layer_idx - 1,
+                    previous_out_dim
+                )));
+            }
+
+            let bias = match weights.remove(&format!("dense_{}.bias", layer_idx)) {
+                Some(bias) if bias.dims() != [out_dim] => {
+                    return Err(EncoderError::Model(format!(
+                        "dense_{}.bias must be [{}], got {:?}",
+                        layer_idx,
+                        out_dim,
+                        bias.dims()
+                    )));
+                }
+                Some(bias) => Some(bias.to_dtype(DType::F32)?),
+                None => None,
+            };
+            layers.push(Linear::new(weight.to_dtype(DType::F32)?, bias));
+        }
+
+        if layers.is_empty() {
+            return Err(EncoderError::Model(format!(
+                "Safetensors encoder {} has no dense_0.weight tensor",
+                model_path.display()
+            )));
+        }
+
+        // Anything left is a layer this backend would silently skip, e.g. a gap in the numbering
+        if !weights.is_empty() {
+            let mut unused = weights.keys().cloned().collect::<Vec<String>>();
+            unused.sort();
+            return Err(EncoderError::Model(format!(
+                "Safetensors encoder {} has tensors outside the dense_0..dense_{} stack: {}",
+                model_path.display(),
+                layers.len() - 1,
+                unused.join(", ")
+            )));
+        }
+
+        tracing::info!(
+            "Loaded {}-layer candle encoder on {:?}",
+            layers.len(),
+            device
+        );
+
+        Ok(CandleEncoder { device, layers })
+    }
+
+    pub(crate) fn encode(&self, input: &EncoderInput) -> Result<Array2<f32>> {
+        let shape = (input.num_rows(), input.row_length());
+        let mut activations = candle_core::Tensor::from_vec(input.flatten_f32(), shape, &self.device)?;
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            activations = layer.forward(&activations)?;
+            if layer_idx + 1 < self.layers.len() {
+                activations = activations.relu()?;
+            }
+        }
+
+        let (rows, latent_dim) = activations.dims2()?;
+        let values = activations
+            .to_dtype(DType::F32)?
+            .flatten_all()?
+            .to_vec1::<f32>()?;
+
+        Ok(Array2::from_shape_vec((rows, latent_dim), values)?)
+    }
+}
+
+fn select_device() -> Result<Device> {
+    if candle_core::utils::cuda_is_available() {
+        return Ok(Device::new_cuda(0)?);
+    }
+
+    if candle_core::utils::metal_is_available() {
+        return Ok(Device::new_metal(0)?);
+    }
+
+    Ok(Device::Cpu)
+}
+
+impl From<candle_core::Error> for EncoderError {
+    fn from(e: candle_core::Error) -> Self {
+        EncoderError::Model(format!("candle error: {}", e))
+    }
+}
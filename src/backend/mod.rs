@@ -0,0 +1,206 @@
+#[cfg(feature = "candle")]
+mod candle;
+#[cfg(feature = "onnx")]
+mod onnx;
+#[cfg(feature = "tensorflow")]
+pub(crate) mod saved_model;
+#[cfg(feature = "tract")]
+mod tract;
+
+use crate::config::{EncoderBackend, EncoderConfig};
+use crate::error::{EncoderError, Result};
+use crate::fingerprint::PackedFingerprints;
+use ndarray::Array2;
+#[cfg(feature = "tensorflow")]
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[cfg(feature = "candle")]
+use self::candle::CandleEncoder;
+#[cfg(feature = "onnx")]
+use self::onnx::OnnxEncoder;
+#[cfg(feature = "tensorflow")]
+use self::saved_model::TensorFlowEncoder;
+#[cfg(feature = "tract")]
+use self::tract::TractEncoder;
+
+/// Fingerprint rows as handed to a backend, which flattens them straight into its input dtype.
+#[derive(Clone, Copy)]
+pub(crate) enum EncoderInput<'a> {
+    Dense(&'a [Vec<i64>]),
+    Packed(PackedFingerprints<'a>),
+}
+
+impl EncoderInput<'_> {
+    pub(crate) fn num_rows(&self) -> usize {
+        match self {
+            EncoderInput::Dense(input_data) => input_data.len(),
+            EncoderInput::Packed(fingerprints) => fingerprints.num_rows(),
+        }
+    }
+
+    // Only meaningful once `validate_input` has passed
+    pub(crate) fn row_length(&self) -> usize {
+        match self {
+            EncoderInput::Dense(input_data) => input_data.first().map_or(0, Vec::len),
+            EncoderInput::Packed(fingerprints) => fingerprints.num_bits(),
+        }
+    }
+
+    /// Row-major `[num_rows, row_length]` values.
+    pub(crate) fn flatten_i64(&self) -> Vec<i64> {
+        match self {
+            EncoderInput::Dense(input_data) => input_data.concat(),
+            EncoderInput::Packed(fingerprints) => fingerprints.unpack_flat(),
+        }
+    }
+
+    /// Row-major `[num_rows, row_length]` values, cast without an `i64` copy in between.
+    pub(crate) fn flatten_f32(&self) -> Vec<f32> {
+        match self {
+            EncoderInput::Dense(input_data) => input_data
+                .iter()
+                .flat_map(|row| row.iter().map(|&bit| bit as f32))
+                .collect(),
+            EncoderInput::Packed(fingerprints) => fingerprints.unpack_flat(),
+        }
+    }
+}
+
+/// Runs the VAE encoder on whichever runtime the config selects. Cluster assignment is separate
+/// and unaffected by the choice.
+pub(crate) enum Encoder {
+    #[cfg(feature = "tensorflow")]
+    TensorFlow(TensorFlowEncoder),
+    #[cfg(feature = "candle")]
+    Candle(CandleEncoder),
+    #[cfg(feature = "onnx")]
+    Onnx(OnnxEncoder),
+    #[cfg(feature = "tract")]
+    Tract(TractEncoder),
+}
+
+impl Encoder {
+    pub(crate) fn load(assets_path: &str, config: &EncoderConfig) -> Result<Self> {
+        let encoder = match config.encoder_backend {
+            #[cfg(feature = "tensorflow")]
+            EncoderBackend::TensorFlow => Encoder::TensorFlow(TensorFlowEncoder::load(assets_path, config)?),
+            #[cfg(feature = "candle")]
+            EncoderBackend::Candle => Encoder::Candle(CandleEncoder::load(&model_asset_path(assets_path, config))?),
+            #[cfg(feature = "onnx")]
+            EncoderBackend::Onnx => Encoder::Onnx(OnnxEncoder::load(&model_asset_path(assets_path, config))?),
+            #[cfg(feature = "tract")]
+            EncoderBackend::Tract => Encoder::Tract(TractEncoder::load(&model_asset_path(assets_path, config))?),
+        };
+
+        Ok(encoder)
+    }
+
+    /// Fingerprint length the loaded model declares, if it has a static one.
+    pub(crate) fn input_dim(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "tensorflow")]
+            Encoder::TensorFlow(encoder) => encoder.input_dim(),
+            #[cfg(feature = "candle")]
+            Encoder::Candle(encoder) => encoder.input_dim(),
+            #[cfg(feature = "onnx")]
+            Encoder::Onnx(encoder) => encoder.input_dim(),
+            #[cfg(feature = "tract")]
+            Encoder::Tract(encoder) => encoder.input_dim(),
+        }
+    }
+
+    /// Latent width the loaded model declares, if it has a static one.
+    pub(crate) fn latent_dim(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "tensorflow")]
+            Encoder::TensorFlow(encoder) => encoder.latent_dim(),
+            #[cfg(feature = "candle")]
+            Encoder::Candle(encoder) => encoder.latent_dim(),
+            #[cfg(feature = "onnx")]
+            Encoder::Onnx(encoder) => encoder.latent_dim(),
+            #[cfg(feature = "tract")]
+            Encoder::Tract(encoder) => encoder.latent_dim(),
+        }
+    }
+
+    /// `[rows, latent_dim]` latents, in standard layout, for `[rows, fingerprint_length]`
+    /// fingerprints.
+    pub(crate) fn encode(&self, input: &EncoderInput) -> Result<Array2<f32>> {
+        validate_input(input, self.input_dim())?;
+
+        match self {
+            #[cfg(feature = "tensorflow")]
+            Encoder::TensorFlow(encoder) => encoder.encode(input),
+            #[cfg(feature = "candle")]
+            Encoder::Candle(encoder) => encoder.encode(input),
+            #[cfg(feature = "onnx")]
+            Encoder::Onnx(encoder) => encoder.encode(input),
+            #[cfg(feature = "tract")]
+            Encoder::Tract(encoder) => encoder.encode(input),
+        }
+    }
+
+    #[cfg(feature = "tensorflow")]
+    pub(crate) fn encode_named(&self, named_inputs: &HashMap<String, Array2<f32>>) -> Result<Array2<f32>> {
+        match self {
+            Encoder::TensorFlow(encoder) => encoder.encode_named(named_inputs),
+            #[allow(unreachable_patterns)]
+            _ => Err(EncoderError::InvalidArgument("Named multi-input encoding needs the TensorFlow backend".to_string())),
+        }
+    }
+}
+
+// Backends flatten rows into one `[rows, row_length]` buffer, so anything else would silently be
+// reshaped into the wrong fingerprints
+fn validate_input(input: &EncoderInput, input_dim: Option<usize>) -> Result<()> {
+    if input.num_rows() == 0 {
+        return Err(EncoderError::ShapeMismatch("Input has no rows".to_string()));
+    }
+
+    let row_length = input.row_length();
+    if let EncoderInput::Dense(input_data) = input {
+        if let Some((row_idx, row)) = input_data.iter().enumerate().find(|(_, row)| row.len() != row_length) {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Input row {} has length {}, but row 0 has length {}",
+                row_idx,
+                row.len(),
+                row_length
+            )));
+        }
+    }
+
+    match input_dim {
+        Some(input_dim) if input_dim != row_length => Err(EncoderError::ShapeMismatch(format!(
+            "Input rows have length {}, but the encoder expects {}",
+            row_length, input_dim
+        ))),
+        None if row_length == 0 => Err(EncoderError::ShapeMismatch("Input rows are empty".to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// The model directory or file the configured backend loads, relative to `assets_path`.
+pub(crate) fn model_asset_path(assets_path: &str, config: &EncoderConfig) -> PathBuf {
+    let assets_path = PathBuf::from(assets_path);
+
+    match config.encoder_backend {
+        #[cfg(feature = "tensorflow")]
+        EncoderBackend::TensorFlow => assets_path.join(&config.encoder_dir),
+        #[cfg(feature = "candle")]
+        EncoderBackend::Candle => assets_path.join(model_file(config, "safetensors")),
+        #[cfg(feature = "onnx")]
+        EncoderBackend::Onnx => assets_path.join(model_file(config, "onnx")),
+        #[cfg(feature = "tract")]
+        EncoderBackend::Tract => assets_path.join(model_file(config, "onnx")),
+    }
+}
+
+// Single-file backends load `model_file`, defaulting to `<encoder_dir>.<extension>`
+#[cfg(any(feature = "candle", feature = "onnx", feature = "tract"))]
+fn model_file(config: &EncoderConfig, extension: &str) -> String {
+    config
+        .model_file
+        .clone()
+        .unwrap_or_else(|| format!("{}.{}", config.encoder_dir, extension))
+}
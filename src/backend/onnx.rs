@@ -0,0 +1,101 @@
+use crate::error::{EncoderError, Result};
+use super::EncoderInput;
+use ndarray::Array2;
+use ort::session::Session;
+use ort::tensor::TensorElementType;
+use ort::value::ValueType;
+use std::path::Path;
+
+/// The VAE encoder exported to ONNX, run through ONNX Runtime. The model must have a single
+/// `[batch, fingerprint_length]` input of int64 or float32 and a single latent output.
+pub(crate) struct OnnxEncoder {
+    session: Session,
+    input_name: String,
+    input_dim: Option<usize>,
+    latent_dim: Option<usize>,
+    float_input: bool,
+}
+
+impl OnnxEncoder {
+    pub(crate) fn load(model_path: &Path) -> Result<Self> {
+        let session = Session::builder()?.commit_from_file(model_path)?;
+
+        if session.inputs.len() != 1 || session.outputs.len() != 1 {
+            return Err(EncoderError::Model(format!(
+                "ONNX encoder {} must have one input and one output, found {} and {}",
+                model_path.display(),
+                session.inputs.len(),
+                session.outputs.len()
+            )));
+        }
+
+        let input = &session.inputs[0];
+        let float_input = match input.input_type.tensor_type() {
+            Some(TensorElementType::Int64) => false,
+            Some(TensorElementType::Float32) => true,
+            other => {
+                return Err(EncoderError::Model(format!(
+                    "ONNX encoder input {} has unsupported element type {:?}",
+                    input.name,
+                    other
+                )))
+            }
+        };
+        let input_name = input.name.clone();
+        let input_dim = static_width(&input.input_type);
+        let latent_dim = static_width(&session.outputs[0].output_type);
+
+        Ok(OnnxEncoder {
+            session,
+            input_name,
+            input_dim,
+            latent_dim,
+            float_input,
+        })
+    }
+
+    pub(crate) fn input_dim(&self) -> Option<usize> {
+        self.input_dim
+    }
+
+    pub(crate) fn latent_dim(&self) -> Option<usize> {
+        self.latent_dim
+    }
+
+    pub(crate) fn encode(&self, input: &EncoderInput) -> Result<Array2<f32>> {
+        let shape = (input.num_rows(), input.row_length());
+
+        let outputs = if self.float_input {
+            let input_array = Array2::<f32>::from_shape_vec(shape, input.flatten_f32())?;
+            self.session.run(ort::inputs![self.input_name.as_str() => input_array]?)?
+        } else {
+            let input_array = Array2::<i64>::from_shape_vec(shape, input.flatten_i64())?;
+            self.session.run(ort::inputs![self.input_name.as_str() => input_array]?)?
+        };
+
+        let latents = outputs[0].try_extract_tensor::<f32>()?;
+        if latents.ndim() != 2 {
+            return Err(EncoderError::Model(format!("ONNX encoder returned a rank {} output", latents.ndim())));
+        }
+
+        let shape = (latents.shape()[0], latents.shape()[1]);
+        let values = latents.iter().copied().collect::<Vec<f32>>();
+
+        Ok(Array2::from_shape_vec(shape, values)?)
+    }
+}
+
+// Second dimension of a `[batch, width]` tensor; dynamic dimensions are reported as -1
+fn static_width(value_type: &ValueType) -> Option<usize> {
+    value_type
+        .tensor_dimensions()
+        .and_then(|dims| dims.get(1).copied())
+        .filter(|&dim| dim > 0)
+        .map(|dim| dim as usize)
+}
+
+impl From<ort::Error> for EncoderError {
+    fn from(e: ort::Error) -> Self {
+        EncoderError::Model(format!("ONNX Runtime error: {}", e))
+    }
+}
@@ -0,0 +1,272 @@
+use crate::config::EncoderConfig;
+use crate::error::{EncoderError, Result};
+use crate::session::session_options;
+use super::EncoderInput;
+use ndarray::Array2;
+use std::collections::HashMap;
+use tensorflow::{DataType, Graph, Output, SavedModelBundle, SessionRunArgs, Tensor, TensorInfo};
+
+/// The VAE encoder as a TF SavedModel, fed through the configured signature.
+pub(crate) struct TensorFlowEncoder {
+    bundle: SavedModelBundle,
+    // Kept alive for the operations below, which point into it
+    _graph: Graph,
+    // The fingerprint feed and its dtype; `None` for multi-input models, see `transform_multi_input`
+    input: Option<(Output, DataType)>,
+    // Fingerprint length the single input declares, if its shape is static
+    input_dim: Option<usize>,
+    inputs: HashMap<String, Output>,
+    output: Output,
+    latent_dim: Option<usize>,
+}
+
+impl TensorFlowEncoder {
+    pub(crate) fn load(assets_path: &str, config: &EncoderConfig) -> Result<Self> {
+        let (bundle, graph) = load_encoder_model(assets_path, config)?;
+        let signature_key = &config.signature_key;
+        let (inputs, output) = match (&config.input_tensor, &config.output_tensor) {
+            // With both tensors named the signature is never read, so models exported without one load
+            (Some(input_tensor), Some(output_tensor)) => {
+                (named_input(&graph, input_tensor)?, resolve_tensor(&graph, output_tensor)?)
+            }
+            (Some(input_tensor), None) => {
+                (named_input(&graph, input_tensor)?, resolve_signature(&bundle, &graph, signature_key)?.1)
+            }
+            (None, Some(output_tensor)) => {
+                (resolve_signature(&bundle, &graph, signature_key)?.0, resolve_tensor(&graph, output_tensor)?)
+            }
+            (None, None) => resolve_signature(&bundle, &graph, signature_key)?,
+        };
+
+        let input = match inputs.values().next() {
+            Some(input) if inputs.len() == 1 => Some((input.clone(), check_input_dtype(input, config)?)),
+            _ => None,
+        };
+        let input_dim = input.as_ref().and_then(|(input, _)| static_width(&graph, input));
+        let latent_dim = static_width(&graph, &output);
+
+        Ok(TensorFlowEncoder {
+            bundle,
+            _graph: graph,
+            input,
+            input_dim,
+            inputs,
+            output,
+            latent_dim,
+        })
+    }
+
+    pub(crate) fn input_dim(&self) -> Option<usize> {
+        self.input_dim
+    }
+
+    pub(crate) fn latent_dim(&self) -> Option<usize> {
+        self.latent_dim
+    }
+
+    pub(crate) fn encode(&self, input: &EncoderInput) -> Result<Array2<f32>> {
+        let (input, input_dtype) = self.input.as_ref().ok_or(EncoderError::InvalidArgument(format!(
+            "Encoder signature has {} inputs; feed them by name with `transform_multi_input`",
+            self.inputs.len()
+        )))?;
+
+        let rows = input.num_rows() as u64;
+        let cols = input.row_length() as u64;
+
+        let int_input_tensor;
+        let float_input_tensor;
+        let mut run_args = SessionRunArgs::new();
+
+        match input_dtype {
+            DataType::Float => {
+                float_input_tensor = Tensor::new(&[rows, cols]).with_values(&input.flatten_f32())?;
+                run_args.add_feed(&input.operation, input.index, &float_input_tensor);
+            },
+            _ => {
+                int_input_tensor = Tensor::new(&[rows, cols]).with_values(&input.flatten_i64())?;
+                run_args.add_feed(&input.operation, input.index, &int_input_tensor);
+            },
+        }
+
+        let output_token = run_args.request_fetch(&self.output.operation, self.output.index);
+        self.bundle.session.run(&mut run_args)?;
+
+        latent_array(run_args.fetch(output_token)?)
+    }
+
+    // Feeds every signature input by name, cast to the dtype its operation expects
+    pub(crate) fn encode_named(&self, named_inputs: &HashMap<String, Array2<f32>>) -> Result<Array2<f32>> {
+        let missing = self
+            .inputs
+            .keys()
+            .filter(|name| !named_inputs.contains_key(*name))
+            .collect::<Vec<&String>>();
+        if !missing.is_empty() {
+            return Err(EncoderError::InvalidArgument(format!("Missing encoder inputs: {:?}", missing)));
+        }
+
+        if let Some(unknown) = named_inputs.keys().find(|name| !self.inputs.contains_key(*name)) {
+            return Err(EncoderError::InvalidArgument(format!("Encoder signature has no input named {}", unknown)));
+        }
+
+        let mut rows = None;
+        let mut float_tensors = Vec::new();
+        let mut int_tensors = Vec::new();
+
+        for (name, values) in named_inputs {
+            if *rows.get_or_insert(values.nrows()) != values.nrows() {
+                return Err(EncoderError::ShapeMismatch(format!(
+                    "Input {} has {} rows, expected {}",
+                    name,
+                    values.nrows(),
+                    rows.unwrap_or_default()
+                )));
+            }
+
+            let input = &self.inputs[name];
+            let dims = [values.nrows() as u64, values.ncols() as u64];
+            let flattened_values = values.iter().copied().collect::<Vec<f32>>();
+
+            match input.operation.output_type(input.index as usize) {
+                DataType::Float => float_tensors.push((input, Tensor::new(&dims).with_values(&flattened_values)?)),
+                DataType::Int64 => {
+                    let cast_values = flattened_values.iter().map(|&value| value as i64).collect::<Vec<i64>>();
+                    int_tensors.push((input, Tensor::new(&dims).with_values(&cast_values)?));
+                },
+                other => return Err(EncoderError::Model(format!("Input {} expects unsupported dtype {}", name, other))),
+            }
+        }
+
+        let mut run_args = SessionRunArgs::new();
+        for (input, tensor) in &float_tensors {
+            run_args.add_feed(&input.operation, input.index, tensor);
+        }
+        for (input, tensor) in &int_tensors {
+            run_args.add_feed(&input.operation, input.index, tensor);
+        }
+
+        let output_token = run_args.request_fetch(&self.output.operation, self.output.index);
+        self.bundle.session.run(&mut run_args)?;
+
+        latent_array(run_args.fetch(output_token)?)
+    }
+}
+
+// Copies the `[rows, latent_dim]` output out of TF's buffer
+fn latent_array(output_tensor: Tensor<f32>) -> Result<Array2<f32>> {
+    let dims = output_tensor.dims();
+    if dims.len() != 2 {
+        return Err(EncoderError::Model(format!("TensorFlow encoder returned a rank {} output", dims.len())));
+    }
+
+    let latents = Array2::from_shape_vec((dims[0] as usize, dims[1] as usize), output_tensor.to_vec())?;
+    Ok(latents)
+}
+
+fn load_encoder_model(assets_path: &str, config: &EncoderConfig) -> Result<(SavedModelBundle, Graph)> {
+    let session_options = session_options(config)?;
+    let mut graph = Graph::new();
+    let model_dir = format!("{}/{}", assets_path, config.encoder_dir);
+    let saved_model = SavedModelBundle::load(&session_options, &config.model_tags, &mut graph, model_dir)?;
+
+    Ok((saved_model, graph))
+}
+
+pub(crate) fn resolve_signature(encoder: &SavedModelBundle, graph: &Graph, signature_key: &str) -> Result<(HashMap<String, Output>, Output)> {
+    let signature = encoder.meta_graph_def().get_signature(signature_key)?;
+
+    if signature.inputs().is_empty() {
+        return Err(EncoderError::Model(format!("Signature {} has no input", signature_key)));
+    }
+
+    let output_info = single_tensor_info(signature.outputs(), signature_key, "output")?;
+
+    let inputs = signature
+        .inputs()
+        .iter()
+        .map(|(name, input_info)| {
+            let input = Output {
+                operation: graph.operation_by_name_required(&input_info.name().name)?,
+                index: input_info.name().index,
+            };
+            Ok((name.clone(), input))
+        })
+        .collect::<Result<HashMap<String, Output>>>()?;
+
+    let output = Output {
+        operation: graph.operation_by_name_required(&output_info.name().name)?,
+        index: output_info.name().index,
+    };
+
+    Ok((inputs, output))
+}
+
+// Accepts TF's `op_name:index` tensor notation, with the index defaulting to 0
+fn resolve_tensor(graph: &Graph, tensor_name: &str) -> Result<Output> {
+    let (op_name, index) = match tensor_name.rsplit_once(':') {
+        Some((op_name, index)) => {
+            let index = index
+                .parse::<i32>()
+                .map_err(|e| EncoderError::InvalidArgument(format!("Invalid output index in tensor name {}: {}", tensor_name, e)))?;
+            (op_name, index)
+        }
+        None => (tensor_name, 0),
+    };
+
+    let operation = graph.operation_by_name_required(op_name)?;
+    if index < 0 || index >= operation.num_outputs() as i32 {
+        return Err(EncoderError::InvalidArgument(format!(
+            "Operation {} has {} outputs, cannot fetch output {}",
+            op_name,
+            operation.num_outputs(),
+            index
+        )));
+    }
+
+    Ok(Output { operation, index })
+}
+
+fn named_input(graph: &Graph, tensor_name: &str) -> Result<HashMap<String, Output>> {
+    let input = resolve_tensor(graph, tensor_name)?;
+    Ok(HashMap::from([(tensor_name.to_string(), input)]))
+}
+
+fn single_tensor_info<'a>(
+    tensor_infos: &'a HashMap<String, TensorInfo>,
+    signature_key: &str,
+    kind: &str,
+) -> Result<&'a TensorInfo> {
+    let mut tensor_infos = tensor_infos.values();
+
+    match (tensor_infos.next(), tensor_infos.next()) {
+        (Some(tensor_info), None) => Ok(tensor_info),
+        (None, _) => Err(EncoderError::Model(format!("Signature {} has no {}", signature_key, kind))),
+        (Some(_), Some(_)) => Err(EncoderError::Model(format!(
+            "Signature {} has more than one {}",
+            signature_key,
+            kind
+        ))),
+    }
+}
+
+// Second dimension of a `[batch, width]` tensor; unknown dimensions come back as `None` or -1
+pub(crate) fn static_width(graph: &Graph, tensor: &Output) -> Option<usize> {
+    let shape = graph.tensor_shape(tensor.clone()).ok()?;
+    match shape.dims() {
+        Some(2) => shape[1].filter(|&dim| dim > 0).map(|dim| dim as usize),
+        _ => None,
+    }
+}
+
+fn check_input_dtype(input: &Output, config: &EncoderConfig) -> Result<DataType> {
+    match input.operation.output_type(input.index as usize) {
+        DataType::Int64 => Ok(DataType::Int64),
+        DataType::Float if config.cast_input => Ok(DataType::Float),
+        other => Err(EncoderError::Model(format!(
+            "Encoder input {} expects {}, but fingerprints are fed as int64{}",
+            input.operation.name()?,
+            other,
+            if other == DataType::Float { "; enable `cast_input` to convert them" } else { "" }
+        ))),
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::{EncoderError, Result};
+use super::EncoderInput;
+use ndarray::Array2;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+type TractPlan = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
+
+/// The VAE encoder exported to ONNX, run by tract entirely in Rust, so no native inference
+/// library needs to be linked for encoding.
+pub(crate) struct TractEncoder {
+    plan: TractPlan,
+    input_dim: Option<usize>,
+    latent_dim: Option<usize>,
+    float_input: bool,
+}
+
+impl TractEncoder {
+    pub(crate) fn load(model_path: &Path) -> Result<Self> {
+        let plan = tract_onnx::onnx()
+            .model_for_path(model_path)
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|e| EncoderError::Model(format!("Failed to load ONNX encoder {}: {}", model_path.display(), e)))?;
+
+        let input_fact = plan
+            .model()
+            .input_fact(0)
+            .map_err(|e| EncoderError::Model(format!("ONNX encoder has no input: {}", e)))?;
+        let input_dim = static_width(input_fact);
+        let latent_dim = plan.model().output_fact(0).ok().and_then(static_width);
+
+        let float_input = match input_fact.datum_type {
+            DatumType::I64 => false,
+            DatumType::F32 => true,
+            other => return Err(EncoderError::Model(format!("ONNX encoder input has unsupported type {:?}", other))),
+        };
+
+        Ok(TractEncoder {
+            plan,
+            input_dim,
+            latent_dim,
+            float_input,
+        })
+    }
+
+    pub(crate) fn input_dim(&self) -> Option<usize> {
+        self.input_dim
+    }
+
+    pub(crate) fn latent_dim(&self) -> Option<usize> {
+        self.latent_dim
+    }
+
+    pub(crate) fn encode(&self, input: &EncoderInput) -> Result<Array2<f32>> {
+        let shape = [input.num_rows(), input.row_length()];
+
+        let input = if self.float_input {
+            tract_onnx::prelude::Tensor::from_shape(&shape, &input.flatten_f32())
+        } else {
+            tract_onnx::prelude::Tensor::from_shape(&shape, &input.flatten_i64())
+        }
+        .map_err(|e| EncoderError::ShapeMismatch(e.to_string()))?;
+
+        let outputs = self
+            .plan
+            .run(tvec!(input.into()))
+            .map_err(|e| EncoderError::Model(format!("tract encoder run failed: {}", e)))?;
+
+        let latents = outputs[0]
+            .to_array_view::<f32>()
+            .map_err(|e| EncoderError::Model(format!("tract encoder returned a non-f32 output: {}", e)))?;
+        if latents.ndim() != 2 {
+            return Err(EncoderError::Model(format!("tract encoder returned a rank {} output", latents.ndim())));
+        }
+
+        let shape = (latents.shape()[0], latents.shape()[1]);
+        let values = latents.iter().copied().collect::<Vec<f32>>();
+
+        Ok(Array2::from_shape_vec(shape, values)?)
+    }
+}
+
+// Second dimension of a `[batch, width]` fact, if it doesn't depend on a symbol
+fn static_width(fact: &TypedFact) -> Option<usize> {
+    match fact.shape.rank() {
+        2 => fact.shape[1].to_usize().ok(),
+        _ => None,
+    }
+}
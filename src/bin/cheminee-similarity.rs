@@ -0,0 +1,151 @@
+use cheminee_similarity_model::encoder::{build_encoder_model, build_encoder_model_from_path, EncoderModel};
+use cheminee_similarity_model::error::{EncoderError, Result};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+const USAGE: &str = "Usage: cheminee-similarity [--format csv|jsonl] [--top-n N] [--assets DIR] [FILE]
+
+Reads one fingerprint per line from FILE, or stdin when FILE is omitted or `-`, and writes the
+comma-separated cluster labels of each row to stdout. CSV rows are comma-separated bits; JSONL
+rows are JSON arrays of bits. The format defaults to the file extension, or CSV for stdin.";
+
+const BATCH_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InputFormat {
+    Csv,
+    Jsonl,
+}
+
+struct Args {
+    format: Option<InputFormat>,
+    top_n: usize,
+    assets_path: Option<String>,
+    input_path: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let mut parsed = Args {
+        format: None,
+        top_n: 1,
+        assets_path: None,
+        input_path: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--format" => {
+                parsed.format = Some(match flag_value(&mut args, &arg)?.as_str() {
+                    "csv" => InputFormat::Csv,
+                    "jsonl" => InputFormat::Jsonl,
+                    other => {
+                        return Err(EncoderError::InvalidArgument(format!("Unknown input format: {other}")))
+                    }
+                })
+            }
+            "--top-n" => {
+                let value = flag_value(&mut args, &arg)?;
+                parsed.top_n = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|top_n| *top_n > 0)
+                    .ok_or_else(|| EncoderError::InvalidArgument(format!("Invalid --top-n: {value}")))?;
+            }
+            "--assets" => parsed.assets_path = Some(flag_value(&mut args, &arg)?),
+            "-" => parsed.input_path = None,
+            flag if flag.starts_with("--") => {
+                return Err(EncoderError::InvalidArgument(format!("Unknown flag: {flag}\n\n{USAGE}")))
+            }
+            _ if parsed.input_path.is_some() => {
+                return Err(EncoderError::InvalidArgument(format!("Unexpected argument: {arg}\n\n{USAGE}")))
+            }
+            _ => parsed.input_path = Some(arg),
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn flag_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next()
+        .ok_or_else(|| EncoderError::InvalidArgument(format!("Missing value for {flag}")))
+}
+
+fn parse_row(line: &str, format: InputFormat, line_number: usize) -> Result<Vec<i64>> {
+    let row = match format {
+        InputFormat::Csv => line
+            .split(',')
+            .map(|value| value.trim().parse::<i64>().map_err(|e| e.to_string()))
+            .collect::<std::result::Result<Vec<i64>, String>>(),
+        InputFormat::Jsonl => serde_json::from_str::<Vec<i64>>(line).map_err(|e| e.to_string()),
+    };
+
+    row.map_err(|e| EncoderError::InvalidArgument(format!("Line {line_number}: {e}")))
+}
+
+fn write_labels(model: &EncoderModel, rows: &[Vec<i64>], top_n: usize, output: &mut impl Write) -> Result<()> {
+    for labels in model.transform_top_n(rows, top_n)? {
+        let line = labels
+            .iter()
+            .map(|label| label.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        writeln!(output, "{line}")?;
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<()> {
+    let args = parse_args(std::env::args().skip(1))?;
+
+    let format = args.format.unwrap_or(match &args.input_path {
+        Some(path) if path.ends_with(".jsonl") || path.ends_with(".json") => InputFormat::Jsonl,
+        _ => InputFormat::Csv,
+    });
+
+    let input: Box<dyn BufRead> = match &args.input_path {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin().lock())),
+    };
+
+    let model = match &args.assets_path {
+        Some(path) => build_encoder_model_from_path(path)?,
+        None => build_encoder_model()?,
+    };
+
+    let mut output = BufWriter::new(io::stdout().lock());
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        batch.push(parse_row(&line, format, line_index + 1)?);
+
+        if batch.len() == BATCH_SIZE {
+            write_labels(&model, &batch, args.top_n, &mut output)?;
+            batch.clear();
+        }
+    }
+
+    if !batch.is_empty() {
+        write_labels(&model, &batch, args.top_n, &mut output)?;
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("cheminee-similarity: {e}");
+        std::process::exit(1);
+    }
+}
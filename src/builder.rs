@@ -0,0 +1,90 @@
+use crate::config::{AssignmentBackend, EncoderBackend, EncoderConfig};
+use crate::encoder::{build_encoder_model_with_config, load_model_from_assets, EncoderModel};
+use crate::error::Result;
+use crate::metric::DistanceMetric;
+
+/// Configures and loads an `EncoderModel` in one chain, e.g.
+/// `EncoderModelBuilder::new().assets_path("assets").metric(DistanceMetric::Cosine).top_n(5).build()`.
+/// Anything without a method here can be set on an `EncoderConfig` passed to `config`.
+#[derive(Clone, Debug, Default)]
+pub struct EncoderModelBuilder {
+    assets_path: Option<String>,
+    config: EncoderConfig,
+}
+
+impl EncoderModelBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces every setting made so far except the assets path.
+    pub fn config(mut self, config: EncoderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Assets directory to load from; defaults to the one `get_assets_path` finds.
+    pub fn assets_path(mut self, assets_path: impl Into<String>) -> Self {
+        self.assets_path = Some(assets_path.into());
+        self
+    }
+
+    pub fn encoder_backend(mut self, encoder_backend: EncoderBackend) -> Self {
+        self.config.encoder_backend = encoder_backend;
+        self
+    }
+
+    pub fn assignment_backend(mut self, assignment_backend: AssignmentBackend) -> Self {
+        self.config.assignment_backend = assignment_backend;
+        self
+    }
+
+    /// Centroid file relative to the assets path, read instead of the bundled one.
+    pub fn centroids_file(mut self, centroids_file: impl Into<String>) -> Self {
+        self.config.centroids_file = centroids_file.into();
+        self.config.embedded_centroids = false;
+        self
+    }
+
+    /// Assign against the centroids compiled in with the `embedded-centroids` feature.
+    pub fn embedded_centroids(mut self, embedded_centroids: bool) -> Self {
+        self.config.embedded_centroids = embedded_centroids;
+        self
+    }
+
+    pub fn intra_op_threads(mut self, threads: usize) -> Self {
+        self.config.session.intra_op_threads = Some(threads);
+        self
+    }
+
+    pub fn inter_op_threads(mut self, threads: usize) -> Self {
+        self.config.session.inter_op_threads = Some(threads);
+        self
+    }
+
+    pub fn metric(mut self, metric: DistanceMetric) -> Self {
+        self.config.metric = metric;
+        self
+    }
+
+    /// Clusters `transform` and `transform_with_distances` return per row by default.
+    pub fn top_n(mut self, top_n: usize) -> Self {
+        self.config.top_n = Some(top_n);
+        self
+    }
+
+    /// Loads the model; invalid settings are reported together, see `EncoderConfig::validate`.
+    pub fn build(self) -> Result<EncoderModel> {
+        match &self.assets_path {
+            Some(assets_path) => load_model_from_assets(assets_path, self.config),
+            None => build_encoder_model_with_config(self.config),
+        }
+    }
+
+    /// Like `build`, but also warms the model up, as `init_encoder_model` does.
+    pub fn init(self) -> Result<EncoderModel> {
+        let encoder_model = self.build()?;
+        encoder_model.warm_up()?;
+        Ok(encoder_model)
+    }
+}
@@ -0,0 +1,364 @@
+use crate::csv_rows::parse_float_rows;
+use crate::error::{EncoderError, Result};
+use crate::metric::DistanceMetric;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ndarray::{Array2, Ix2, OwnedRepr};
+use ndarray_npy::{read_npy, NpzReader, ReadNpyError, ReadNpzError};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fs::{read_to_string, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Describes which CSV columns hold centroid coordinates and which hold per-cluster metadata.
+#[derive(Clone, Debug, Default)]
+pub struct CentroidLayout {
+    /// Coordinate columns, in order; `None` means every column not claimed as metadata
+    pub coordinate_columns: Option<Vec<usize>>,
+    /// Column holding the cluster radius, if the export interleaves one
+    pub radius_column: Option<usize>,
+}
+
+/// Which centroids take part in assignment. Anything but `Full` trades ranking accuracy for
+/// speed: queries whose true nearest cluster was not sampled get the closest sampled one instead.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CentroidSampling {
+    #[default]
+    Full,
+    /// Every `step`-th centroid, starting at cluster 0
+    Strided { step: usize },
+    /// `count` centroids chosen uniformly at random; the same seed always picks the same subset
+    Random { count: usize, seed: u64 },
+}
+
+/// Calibration shipped alongside a centroid file as a JSON sidecar with the same stem.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct CentroidMetadata {
+    /// Softmax temperature fitted for the exponential weight kernel
+    pub temperature: Option<f32>,
+}
+
+pub struct Centroids {
+    /// `[num_clusters, dim]`, one centroid per row
+    pub coordinates: Array2<f32>,
+    pub radii: Option<Vec<f32>>,
+}
+
+impl Centroids {
+    pub fn num_clusters(&self) -> usize {
+        self.coordinates.nrows()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.coordinates.ncols()
+    }
+
+    /// Pairs of cluster ids `(a, b)`, `a < b`, whose centroids lie within `epsilon` of each other
+    /// under the RMS distance used for assignment.
+    pub fn find_duplicates(&self, epsilon: f32) -> Vec<(i32, i32)> {
+        let dim = self.dim();
+        let coordinates = row_major(&self.coordinates);
+        let rows = coordinates.chunks(dim).collect::<Vec<&[f32]>>();
+
+        // An RMS distance within epsilon bounds any single coordinate gap by epsilon * sqrt(dim),
+        // so only neighbours in first-coordinate order need a full comparison
+        let window = epsilon * (dim as f32).sqrt();
+        let mut order = (0..rows.len()).collect::<Vec<usize>>();
+        order.sort_by(|&a, &b| rows[a][0].total_cmp(&rows[b][0]));
+
+        let mut duplicates = Vec::new();
+        for (pos, &a) in order.iter().enumerate() {
+            for &b in order[pos + 1..]
+                .iter()
+                .take_while(|&&b| rows[b][0] - rows[a][0] <= window)
+            {
+                if DistanceMetric::Euclidean.distance(rows[a], rows[b]) <= epsilon {
+                    duplicates.push((a.min(b) as i32, a.max(b) as i32));
+                }
+            }
+        }
+
+        duplicates.sort_unstable();
+        duplicates
+    }
+
+    /// Returns the sampled coordinates together with the original cluster id of each sampled row.
+    pub fn subsample(&self, sampling: &CentroidSampling) -> Result<(Array2<f32>, Vec<i32>)> {
+        let num_clusters = self.num_clusters();
+
+        let cluster_ids: Vec<usize> = match sampling {
+            CentroidSampling::Full => (0..num_clusters).collect(),
+            CentroidSampling::Strided { step } => {
+                if *step == 0 {
+                    return Err(EncoderError::InvalidArgument("Centroid sampling step must be positive".to_string()));
+                }
+                (0..num_clusters).step_by(*step).collect()
+            }
+            CentroidSampling::Random { count, seed } => {
+                if *count == 0 || *count > num_clusters {
+                    return Err(EncoderError::InvalidArgument(format!(
+                        "Cannot sample {} of {} centroids",
+                        count,
+                        num_clusters
+                    )));
+                }
+
+                let mut rng = StdRng::seed_from_u64(*seed);
+                let mut ids = rand::seq::index::sample(&mut rng, num_clusters, *count).into_vec();
+                ids.sort_unstable();
+                ids
+            }
+        };
+
+        let dim = self.dim();
+        let all_coordinates = row_major(&self.coordinates);
+        let coordinates = cluster_ids
+            .iter()
+            .flat_map(|&id| all_coordinates[id * dim..(id + 1) * dim].iter().copied())
+            .collect::<Vec<f32>>();
+
+        let sampled = Array2::from_shape_vec((cluster_ids.len(), dim), coordinates)?;
+        let cluster_ids = cluster_ids.into_iter().map(|id| id as i32).collect();
+
+        Ok((sampled, cluster_ids))
+    }
+}
+
+// Row-major values of `array`. Arrays the crate builds are already in standard layout and are
+// borrowed; only ones a caller built in another order are copied.
+pub(crate) fn row_major(array: &Array2<f32>) -> Cow<'_, [f32]> {
+    match array.as_slice() {
+        Some(values) => Cow::Borrowed(values),
+        None => Cow::Owned(array.iter().copied().collect()),
+    }
+}
+
+#[cfg(feature = "embedded-centroids")]
+static EMBEDDED_CENTROIDS: &[u8] = include_bytes!(env!("CHEMINEE_EMBEDDED_CENTROIDS_PATH"));
+
+pub fn load_centroids_csv(
+    path: &str,
+    layout: &CentroidLayout,
+    latent_dim: usize,
+) -> Result<Centroids> {
+    let contents = read_to_string(path)?;
+    parse_centroids_csv(&contents, path, layout, Some(latent_dim))
+}
+
+/// Writes centroids as a headerless CSV with one centroid per line, the format the bundled
+/// centroids ship in. Radii, when present, go in a trailing column; read them back with
+/// `CentroidLayout { radius_column: Some(dim), .. }`.
+pub fn save_centroids_csv(centroids: &Centroids, path: &str) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    for (cluster, coordinates) in centroids.coordinates.rows().into_iter().enumerate() {
+        let mut row = coordinates.iter().map(|value| value.to_string()).collect::<Vec<String>>();
+        if let Some(radii) = &centroids.radii {
+            row.push(radii[cluster].to_string());
+        }
+        writeln!(writer, "{}", row.join(","))?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a `[num_clusters, latent_dim]` matrix from a `.npy` file, or from the first array in an
+/// `.npz` archive. float64 matrices are narrowed to float32.
+pub fn load_centroids_npy(path: &str) -> Result<Centroids> {
+    let parse_error = |e: &dyn std::fmt::Display| {
+        EncoderError::CentroidParse(format!("Failed to read centroid matrix {}: {}", path, e))
+    };
+
+    let matrix = if path.to_lowercase().ends_with(".npz") {
+        let mut npz = NpzReader::new(File::open(path)?).map_err(|e| parse_error(&e))?;
+        match npz.by_index::<OwnedRepr<f32>, Ix2>(0) {
+            Err(ReadNpzError::Npy(ReadNpyError::WrongDescriptor(_))) => npz
+                .by_index::<OwnedRepr<f64>, Ix2>(0)
+                .map(|matrix| matrix.mapv(|value| value as f32)),
+            matrix => matrix,
+        }
+        .map_err(|e| parse_error(&e))?
+    } else {
+        match read_npy::<_, Array2<f32>>(path) {
+            Err(ReadNpyError::WrongDescriptor(_)) => {
+                read_npy::<_, Array2<f64>>(path).map(|matrix| matrix.mapv(|value| value as f32))
+            }
+            matrix => matrix,
+        }
+        .map_err(|e| parse_error(&e))?
+    };
+
+    if matrix.nrows() == 0 {
+        return Err(EncoderError::CentroidParse(format!("Centroid file {} contains no rows", path)));
+    }
+
+    // `.npy` files may be stored column-major; the crate works on row-major centroids
+    let coordinates = matrix.iter().copied().collect::<Vec<f32>>();
+    let coordinates = Array2::from_shape_vec(matrix.dim(), coordinates)?;
+
+    Ok(Centroids {
+        coordinates,
+        radii: None,
+    })
+}
+
+// Picks the reader by extension: `.npy`/`.npz` matrices ignore `layout`, anything else is CSV.
+// Without a `latent_dim` the width of the file becomes the latent width.
+pub(crate) fn load_centroids_file(path: &str, layout: &CentroidLayout, latent_dim: Option<usize>) -> Result<Centroids> {
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    match extension.as_deref() {
+        Some("npy") | Some("npz") => {
+            let centroids = load_centroids_npy(path)?;
+            if let Some(latent_dim) = latent_dim.filter(|&dim| dim != centroids.dim()) {
+                return Err(EncoderError::ShapeMismatch(format!(
+                    "Centroid matrix {} has {} columns but the latent dimension is {}",
+                    path,
+                    centroids.dim(),
+                    latent_dim
+                )));
+            }
+
+            Ok(centroids)
+        }
+        _ => {
+            let contents = read_to_string(path)?;
+            parse_centroids_csv(&contents, path, layout, latent_dim)
+        }
+    }
+}
+
+/// Parses the centroid CSV compiled in with the `embedded-centroids` feature, without touching
+/// the filesystem.
+#[cfg(feature = "embedded-centroids")]
+pub fn load_centroids_from_embedded(layout: &CentroidLayout) -> Result<Centroids> {
+    let contents = std::str::from_utf8(EMBEDDED_CENTROIDS)
+        .map_err(|e| EncoderError::CentroidParse(format!("Embedded centroids are not valid UTF-8: {}", e)))?;
+
+    parse_centroids_csv(
+        contents,
+        "<embedded>",
+        layout,
+        None,
+    )
+}
+
+fn parse_centroids_csv(
+    contents: &str,
+    source: &str,
+    layout: &CentroidLayout,
+    latent_dim: Option<usize>,
+) -> Result<Centroids> {
+    let (values, row_width) = parse_float_rows(contents)?;
+    if values.is_empty() {
+        return Err(EncoderError::CentroidParse(format!("Centroid file {} contains no rows", source)));
+    }
+
+    let coordinate_columns = coordinate_columns(layout, row_width)?;
+    if let Some(latent_dim) = latent_dim.filter(|&dim| dim != coordinate_columns.len()) {
+        return Err(EncoderError::ShapeMismatch(format!(
+            "Centroid layout selects {} coordinate columns but the latent dimension is {}",
+            coordinate_columns.len(),
+            latent_dim
+        )));
+    }
+
+    let num_rows = values.len() / row_width;
+    let coordinates = values
+        .chunks(row_width)
+        .flat_map(|row| coordinate_columns.iter().map(move |&col| row[col]))
+        .collect::<Vec<f32>>();
+    let coordinates = Array2::from_shape_vec((num_rows, coordinate_columns.len()), coordinates)?;
+    let radii = layout
+        .radius_column
+        .map(|radius_column| values.chunks(row_width).map(|row| row[radius_column]).collect());
+
+    Ok(Centroids {
+        coordinates,
+        radii,
+    })
+}
+
+/// Reads the `.json` sidecar next to `centroids_path`, if there is one.
+pub fn load_centroid_metadata(centroids_path: &str) -> Result<Option<CentroidMetadata>> {
+    let metadata_path = Path::new(centroids_path).with_extension("json");
+    if !metadata_path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = read_to_string(&metadata_path)?;
+    let metadata = serde_json::from_str(&contents).map_err(|e| {
+        EncoderError::CentroidParse(format!("Failed to parse centroid metadata {}: {}", metadata_path.display(), e))
+    })?;
+
+    Ok(Some(metadata))
+}
+
+/// Builds a `[num_rows, width]` centroid matrix from lazily produced rows, copying each row into
+/// one flat buffer as it arrives. Every row must have the width of the first.
+pub fn load_centroids_from_rows(rows: impl Iterator<Item = Vec<f32>>) -> Result<Array2<f32>> {
+    let mut coordinates = Vec::new();
+    let mut row_width = None;
+    let mut num_rows = 0;
+
+    for (row_idx, row) in rows.enumerate() {
+        let width = *row_width.get_or_insert(row.len());
+        if width == 0 {
+            return Err(EncoderError::ShapeMismatch("Centroid rows must not be empty".to_string()));
+        }
+
+        if row.len() != width {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Centroid row {} has {} columns, expected {}",
+                row_idx,
+                row.len(),
+                width
+            )));
+        }
+
+        coordinates.extend_from_slice(&row);
+        num_rows += 1;
+    }
+
+    let width = row_width.ok_or(EncoderError::ShapeMismatch("No centroid rows to load".to_string()))?;
+    let coordinates = Array2::from_shape_vec((num_rows, width), coordinates)?;
+
+    Ok(coordinates)
+}
+
+fn coordinate_columns(layout: &CentroidLayout, row_width: usize) -> Result<Vec<usize>> {
+    if let Some(radius_column) = layout.radius_column {
+        if radius_column >= row_width {
+            return Err(EncoderError::InvalidArgument(format!(
+                "Radius column {} is out of bounds for rows with {} columns",
+                radius_column,
+                row_width
+            )));
+        }
+    }
+
+    match &layout.coordinate_columns {
+        Some(columns) => {
+            if let Some(&col) = columns.iter().find(|&&col| col >= row_width) {
+                return Err(EncoderError::InvalidArgument(format!(
+                    "Coordinate column {} is out of bounds for rows with {} columns",
+                    col,
+                    row_width
+                )));
+            }
+
+            if layout.radius_column.is_some_and(|radius| columns.contains(&radius)) {
+                return Err(EncoderError::InvalidArgument("Radius column overlaps the coordinate columns".to_string()));
+            }
+
+            Ok(columns.clone())
+        }
+        None => Ok((0..row_width)
+            .filter(|&col| Some(col) != layout.radius_column)
+            .collect()),
+    }
+}
@@ -0,0 +1,289 @@
+use crate::ann::HnswConfig;
+use crate::centroids::{CentroidLayout, CentroidSampling};
+#[cfg(feature = "tensorflow")]
+use crate::decoder::DECODER_DIR;
+use crate::encoder::{CENTROIDS_FILE, ENCODER_DIR};
+use crate::error::{EncoderError, Result};
+use crate::kernel::{WeightClamp, WeightKernel};
+use crate::metric::DistanceMetric;
+use crate::outlier::OutlierThresholds;
+
+#[derive(Clone, Debug)]
+pub struct EncoderConfig {
+    pub centroid_layout: CentroidLayout,
+    pub kernel: WeightKernel,
+    /// Replace an exponential kernel's temperature with the calibrated one from the centroid
+    /// metadata sidecar when the sidecar provides it
+    pub metadata_temperature: bool,
+    pub weight_clamp: WeightClamp,
+    pub metric: DistanceMetric,
+    /// Opt-in bfloat16 execution of the encoder matmuls via TF's oneDNN auto mixed precision
+    /// rewrite. Only takes effect on CPUs with native bf16 support; latents lose roughly three
+    /// significant digits, which can swap near-tied clusters in the ranking. TensorFlow encoder only.
+    pub bfloat16: bool,
+    /// Threading and GPU settings for every TensorFlow session the model creates
+    pub session: SessionConfig,
+    pub outlier_thresholds: OutlierThresholds,
+    pub nan_policy: NanPolicy,
+    /// Feed fingerprints as float32 when the exported model's input expects it
+    pub cast_input: bool,
+    /// Count top-1 assignments per cluster in `transform`, see `cluster_usage_counts`
+    pub track_usage: bool,
+    /// Nearest clusters `transform`, `transform_packed` and `transform_with_distances` return per
+    /// row; `None` ranks every cluster
+    pub top_n: Option<usize>,
+    pub centroid_sampling: CentroidSampling,
+    pub assignment_backend: AssignmentBackend,
+    /// Rank only the nearest clusters with TF's `ApproxTopK` instead of sorting all of them
+    pub approx_top_k: Option<ApproxTopK>,
+    /// Log centroid pairs closer than this at load time, see `Centroids::find_duplicates`
+    pub duplicate_centroid_epsilon: Option<f32>,
+    /// Build an HNSW index over the centroids at load time for `transform_approximate`
+    pub hnsw: Option<HnswConfig>,
+    pub encoder_backend: EncoderBackend,
+    /// Model file for single-file backends, relative to the assets path; defaults to
+    /// `encoder_dir` plus the backend's extension, e.g. `vae_encoder.onnx`
+    pub model_file: Option<String>,
+    /// SavedModel tag set to load
+    pub model_tags: Vec<String>,
+    /// Signature whose inputs and single output are discovered as the encoder's feeds and fetch
+    pub signature_key: String,
+    /// Feed fingerprints to this `op_name[:index]` tensor instead of the signature input, e.g.
+    /// `serving_default_dense_input` for the bundled model
+    pub input_tensor: Option<String>,
+    /// Fetch this `op_name[:index]` tensor instead of the signature output, e.g. to stop at the
+    /// latent when the model bundles postprocessing. Only ops visible in the loaded graph can be
+    /// named; anything inlined in a `StatefulPartitionedCall` function body is not
+    pub output_tensor: Option<String>,
+    /// SavedModel directory, relative to the assets path
+    pub encoder_dir: String,
+    /// Centroid file, relative to the assets path: a `.npy`/`.npz` matrix or a CSV
+    pub centroids_file: String,
+    /// Assign against the centroids compiled in with the `embedded-centroids` feature instead of
+    /// reading `centroids_file`. Parsed with the same `centroid_layout`
+    pub embedded_centroids: bool,
+    /// Per-cluster covariance `.npy`, relative to the assets path, for `DistanceMetric::Mahalanobis`;
+    /// see `load_cluster_covariance` for the accepted shapes
+    pub cluster_covariance_file: Option<String>,
+    /// Second centroid file, relative to the assets path, for `ClusterSet::Coarse` assignment.
+    /// Read with the same `centroid_layout`; sampling, HNSW and usage tracking don't apply to it.
+    pub coarse_centroids_file: Option<String>,
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        EncoderConfig {
+            centroid_layout: CentroidLayout::default(),
+            kernel: WeightKernel::default(),
+            metadata_temperature: true,
+            weight_clamp: WeightClamp::default(),
+            metric: DistanceMetric::default(),
+            bfloat16: false,
+            session: SessionConfig::default(),
+            outlier_thresholds: OutlierThresholds::default(),
+            nan_policy: NanPolicy::default(),
+            cast_input: false,
+            track_usage: false,
+            top_n: None,
+            centroid_sampling: CentroidSampling::default(),
+            assignment_backend: AssignmentBackend::default(),
+            approx_top_k: None,
+            duplicate_centroid_epsilon: None,
+            hnsw: None,
+            encoder_backend: EncoderBackend::default(),
+            model_file: None,
+            model_tags: vec!["serve".to_string()],
+            signature_key: "serving_default".to_string(),
+            input_tensor: None,
+            output_tensor: None,
+            encoder_dir: ENCODER_DIR.to_string(),
+            centroids_file: CENTROIDS_FILE.to_string(),
+            embedded_centroids: false,
+            cluster_covariance_file: None,
+            coarse_centroids_file: None,
+        }
+    }
+}
+
+impl EncoderConfig {
+    /// Checks every setting that can be judged without loading anything, reporting all invalid
+    /// ones in a single `InvalidArgument`. Model loading runs this first.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if self.top_n == Some(0) {
+            problems.push("top_n must be at least 1".to_string());
+        }
+        if self.session.intra_op_threads == Some(0) || self.session.inter_op_threads == Some(0) {
+            problems.push("thread counts must be at least 1".to_string());
+        }
+        if let Some(fraction) = self.session.gpu_memory_fraction.filter(|fraction| !(*fraction > 0.0 && *fraction <= 1.0)) {
+            problems.push(format!("GPU memory fraction must be in (0, 1], got {}", fraction));
+        }
+        #[cfg(feature = "tensorflow")]
+        let tensorflow_encoder = self.encoder_backend == EncoderBackend::TensorFlow;
+        #[cfg(not(feature = "tensorflow"))]
+        let tensorflow_encoder = false;
+        if self.bfloat16 && !tensorflow_encoder {
+            problems.push(format!("bfloat16 needs the TensorFlow encoder backend, got {:?}", self.encoder_backend));
+        }
+        if let Some(approx_top_k) = &self.approx_top_k {
+            if approx_top_k.k == 0 {
+                problems.push("approx_top_k.k must be at least 1".to_string());
+            }
+            if !(approx_top_k.recall_target > 0.0 && approx_top_k.recall_target <= 1.0) {
+                problems.push(format!("approx_top_k.recall_target must be in (0, 1], got {}", approx_top_k.recall_target));
+            }
+        }
+        if let Some(hnsw) = &self.hnsw {
+            if hnsw.max_connections == 0 || hnsw.ef_construction == 0 || hnsw.ef_search == 0 {
+                problems.push("HNSW max_connections, ef_construction and ef_search must be at least 1".to_string());
+            }
+        }
+        if let Some(epsilon) = self.duplicate_centroid_epsilon.filter(|epsilon| !(*epsilon >= 0.0)) {
+            problems.push(format!("duplicate_centroid_epsilon must be non-negative, got {}", epsilon));
+        }
+        if self.encoder_dir.is_empty() {
+            problems.push("encoder_dir is empty".to_string());
+        }
+        if self.centroids_file.is_empty() {
+            problems.push("centroids_file is empty".to_string());
+        }
+        if self.embedded_centroids && !cfg!(feature = "embedded-centroids") {
+            problems.push("embedded_centroids needs the embedded-centroids feature".to_string());
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(EncoderError::InvalidArgument(format!("Invalid encoder config: {}", problems.join("; "))))
+    }
+}
+
+/// Runtime the VAE encoder runs on. Only the TensorFlow backend supports signature selection,
+/// `output_tensor` and multi-input models. Defaults to TensorFlow, or without it to the first of
+/// ONNX Runtime, tract and candle that is compiled in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncoderBackend {
+    /// The SavedModel in `encoder_dir`
+    #[cfg(feature = "tensorflow")]
+    #[default]
+    TensorFlow,
+    /// Dense encoder weights converted to safetensors, run with candle on CUDA, Metal or CPU
+    #[cfg(feature = "candle")]
+    #[cfg_attr(not(any(feature = "tensorflow", feature = "onnx", feature = "tract")), default)]
+    Candle,
+    /// An ONNX export of the encoder, run with ONNX Runtime
+    #[cfg(feature = "onnx")]
+    #[cfg_attr(not(feature = "tensorflow"), default)]
+    Onnx,
+    /// An ONNX export of the encoder, run with tract in pure Rust
+    #[cfg(feature = "tract")]
+    #[cfg_attr(not(any(feature = "tensorflow", feature = "onnx")), default)]
+    Tract,
+}
+
+/// How `DecoderModel` loads the VAE decoder SavedModel.
+#[cfg(feature = "tensorflow")]
+#[derive(Clone, Debug)]
+pub struct DecoderConfig {
+    /// SavedModel directory, relative to the assets path
+    pub decoder_dir: String,
+    pub model_tags: Vec<String>,
+    /// Signature whose single input and output are the latent feed and reconstruction fetch
+    pub signature_key: String,
+    /// Apply a sigmoid to the output, for decoders exported without their final activation
+    pub logits: bool,
+    pub session: SessionConfig,
+}
+
+#[cfg(feature = "tensorflow")]
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        DecoderConfig {
+            decoder_dir: DECODER_DIR.to_string(),
+            model_tags: vec!["serve".to_string()],
+            signature_key: "serving_default".to_string(),
+            logits: false,
+            session: SessionConfig::default(),
+        }
+    }
+}
+
+/// TensorFlow `ConfigProto` settings; the defaults leave TensorFlow's own choices in place, which
+/// include reserving nearly all memory on every visible GPU.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionConfig {
+    /// Threads used within a single op, e.g. one matmul; `None` lets TensorFlow pick
+    pub intra_op_threads: Option<usize>,
+    /// Threads used to run independent ops concurrently; `None` lets TensorFlow pick
+    pub inter_op_threads: Option<usize>,
+    /// Allocate GPU memory as needed instead of reserving it up front
+    pub allow_growth: bool,
+    /// Upper bound on the share of each GPU's memory the process may reserve, in `(0, 1]`
+    pub gpu_memory_fraction: Option<f64>,
+    /// Comma-separated GPU ids TensorFlow may use, e.g. `"0"` or `"1,3"`
+    pub visible_devices: Option<String>,
+    /// Run everything on the CPU even when GPUs are available
+    pub disable_gpu: bool,
+    /// Fall back to another device when an op can't run on the one it was placed on
+    pub allow_soft_placement: bool,
+    /// Log the device each op is placed on
+    pub log_device_placement: bool,
+}
+
+/// Where the latent-to-centroid distances and ranking are computed. Defaults to TensorFlow when
+/// the `tensorflow` feature is on and to `Native` otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssignmentBackend {
+    /// A cached TF graph; the only backend that honours `approx_top_k`
+    #[cfg(feature = "tensorflow")]
+    #[default]
+    TensorFlow,
+    /// Plain Rust over the configured `DistanceMetric`, parallel with the `rayon` feature
+    #[cfg_attr(not(feature = "tensorflow"), default)]
+    Native,
+}
+
+/// Partial ranking through TF's `ApproxTopK` op. Rows then carry only `k` clusters, and with
+/// `recall_target < 1.0` some of the true top `k` may be missing or replaced by slightly farther
+/// ones. Falls back to exact `TopKV2` (still truncated to `k`) when the linked TF build has no
+/// `ApproxTopK` op or kernel, checked once per model when its graphs are built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApproxTopK {
+    pub k: usize,
+    pub recall_target: f32,
+}
+
+/// Per-call overrides for `transform_with_options`; `None` fields fall back to the model's config.
+#[derive(Clone, Debug, Default)]
+pub struct TransformOptions {
+    /// Keep only the nearest `top_k` clusters per row; `None` keeps all of them
+    pub top_k: Option<usize>,
+    pub metric: Option<DistanceMetric>,
+    pub kernel: Option<WeightKernel>,
+    pub weight_clamp: Option<WeightClamp>,
+}
+
+/// Which partition of the latent space `transform_with` assigns against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClusterSet {
+    /// The centroids from `centroids_file`
+    #[default]
+    Fine,
+    /// The centroids from `coarse_centroids_file`
+    Coarse,
+}
+
+/// What to do with latent rows containing NaN before they reach cluster assignment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// Fail the whole call
+    #[default]
+    Error,
+    /// Return an empty assignment for the affected rows
+    SkipRow,
+    /// Replace NaN components with zero and assign as usual
+    ZeroFill,
+}
@@ -0,0 +1,222 @@
+use crate::error::{EncoderError, Result};
+use ndarray::ArrayD;
+use ndarray_npy::{read_npy, ReadNpyError};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Per-cluster spread for `DistanceMetric::Mahalanobis`, with rows in cluster id order.
+#[derive(Clone, Debug)]
+pub enum ClusterCovariance {
+    /// Inverse of each cluster's per-dimension variance, `[num_clusters, dim]`
+    Diagonal {
+        inverse_variances: Vec<f32>,
+        dim: usize,
+    },
+    /// Lower Cholesky factor of each cluster's covariance, `[num_clusters, dim, dim]`
+    Full {
+        cholesky_factors: Vec<f32>,
+        dim: usize,
+    },
+}
+
+impl ClusterCovariance {
+    pub fn num_clusters(&self) -> usize {
+        match self {
+            ClusterCovariance::Diagonal {
+                inverse_variances,
+                dim,
+            } => inverse_variances.len() / dim,
+            ClusterCovariance::Full {
+                cholesky_factors,
+                dim,
+            } => cholesky_factors.len() / (dim * dim),
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        match self {
+            ClusterCovariance::Diagonal { dim, .. } | ClusterCovariance::Full { dim, .. } => *dim,
+        }
+    }
+
+    /// `sqrt((x - c)^T S^-1 (x - c) / dim)` for cluster `cluster` with centroid `c`, which is the
+    /// RMS distance when `S` is the identity.
+    pub fn distance(&self, cluster: usize, centroid: &[f32], latent: &[f32]) -> f32 {
+        let squared_distance = match self {
+            ClusterCovariance::Diagonal {
+                inverse_variances,
+                dim,
+            } => latent
+                .iter()
+                .zip(centroid)
+                .zip(&inverse_variances[cluster * dim..(cluster + 1) * dim])
+                .map(|((x, c), inverse_variance)| (x - c) * (x - c) * inverse_variance)
+                .sum::<f32>(),
+            ClusterCovariance::Full {
+                cholesky_factors,
+                dim,
+            } => {
+                // Solves `L y = x - c` by forward substitution; then `|y|^2 = (x - c)^T S^-1 (x - c)`
+                let factor = &cholesky_factors[cluster * dim * dim..(cluster + 1) * dim * dim];
+                let mut solved = vec![0.0f32; *dim];
+                for row in 0..*dim {
+                    let partial = (0..row)
+                        .map(|col| factor[row * dim + col] * solved[col])
+                        .sum::<f32>();
+                    solved[row] = (latent[row] - centroid[row] - partial) / factor[row * dim + row];
+                }
+                solved.iter().map(|value| value * value).sum::<f32>()
+            }
+        };
+
+        (squared_distance / latent.len() as f32).sqrt()
+    }
+
+    /// Nearest `top_n` of `centroids` (all when `None`) per latent row. Row `i` of `centroids`
+    /// belongs to cluster `cluster_ids[i]`, or to cluster `i` without ids.
+    pub(crate) fn rank(
+        &self,
+        latents: &[f32],
+        centroids: &[f32],
+        cluster_ids: Option<&[i32]>,
+        top_n: Option<usize>,
+    ) -> Vec<Vec<(i32, f32)>> {
+        let dim = self.dim();
+        let rank_row = |latent: &[f32]| {
+            let mut ranked_clusters = centroids
+                .chunks(dim)
+                .enumerate()
+                .map(|(idx, centroid)| {
+                    let cluster = cluster_ids.map_or(idx, |ids| ids[idx] as usize);
+                    (idx as i32, self.distance(cluster, centroid, latent))
+                })
+                .collect::<Vec<(i32, f32)>>();
+
+            ranked_clusters.sort_by(|a, b| a.1.total_cmp(&b.1));
+            ranked_clusters.truncate(top_n.unwrap_or(ranked_clusters.len()));
+            ranked_clusters
+        };
+
+        #[cfg(feature = "rayon")]
+        let ranked_clusters = latents.par_chunks(dim).map(rank_row).collect();
+        #[cfg(not(feature = "rayon"))]
+        let ranked_clusters = latents.chunks(dim).map(rank_row).collect();
+
+        ranked_clusters
+    }
+
+    /// Inverse variances for `centroids` rows as in `rank`, or `None` for full covariance.
+    pub(crate) fn diagonal_rows(&self, cluster_ids: Option<&[i32]>) -> Option<Vec<f32>> {
+        let ClusterCovariance::Diagonal {
+            inverse_variances,
+            dim,
+        } = self
+        else {
+            return None;
+        };
+
+        let rows = match cluster_ids {
+            Some(ids) => ids
+                .iter()
+                .flat_map(|&id| {
+                    inverse_variances[id as usize * dim..(id as usize + 1) * dim]
+                        .iter()
+                        .copied()
+                })
+                .collect(),
+            None => inverse_variances.clone(),
+        };
+        Some(rows)
+    }
+}
+
+/// Reads per-cluster covariance from a `.npy` file: a `[num_clusters, dim]` matrix of diagonal
+/// variances, or a `[num_clusters, dim, dim]` stack of full covariance matrices. Every variance
+/// must be positive and every full matrix positive definite.
+pub fn load_cluster_covariance(
+    path: &str,
+    num_clusters: usize,
+    dim: usize,
+) -> Result<ClusterCovariance> {
+    let parse_error = |message: String| {
+        EncoderError::CentroidParse(format!("Cluster covariance {}: {}", path, message))
+    };
+
+    let covariance = match read_npy::<_, ArrayD<f32>>(path) {
+        Err(ReadNpyError::WrongDescriptor(_)) => {
+            read_npy::<_, ArrayD<f64>>(path).map(|covariance| covariance.mapv(|value| value as f32))
+        }
+        covariance => covariance,
+    }
+    .map_err(|e| parse_error(e.to_string()))?;
+
+    let shape = covariance.shape().to_vec();
+    let values = covariance.iter().copied().collect::<Vec<f32>>();
+
+    if shape == [num_clusters, dim] {
+        let inverse_variances = values
+            .iter()
+            .map(|&variance| {
+                if variance > 0.0 {
+                    Ok(1.0 / variance)
+                } else {
+                    Err(variance)
+                }
+            })
+            .collect::<std::result::Result<Vec<f32>, f32>>()
+            .map_err(|variance| {
+                parse_error(format!("variances must be positive, found {}", variance))
+            })?;
+
+        Ok(ClusterCovariance::Diagonal {
+            inverse_variances,
+            dim,
+        })
+    } else if shape == [num_clusters, dim, dim] {
+        let mut cholesky_factors = Vec::with_capacity(values.len());
+        for (cluster, matrix) in values.chunks(dim * dim).enumerate() {
+            let factor = cholesky(matrix, dim).ok_or_else(|| {
+                parse_error(format!(
+                    "covariance of cluster {} is not positive definite",
+                    cluster
+                ))
+            })?;
+            cholesky_factors.extend(factor);
+        }
+
+        Ok(ClusterCovariance::Full {
+            cholesky_factors,
+            dim,
+        })
+    } else {
+        Err(parse_error(format!(
+            "expected shape [{}, {}] or [{}, {}, {}], got {:?}",
+            num_clusters, dim, num_clusters, dim, dim, shape
+        )))
+    }
+}
+
+// Lower-triangular `L` with `L L^T = matrix`, accumulated in f64; `None` unless positive definite
+fn cholesky(matrix: &[f32], dim: usize) -> Option<Vec<f32>> {
+    let mut factor = vec![0.0f64; dim * dim];
+
+    for row in 0..dim {
+        for col in 0..=row {
+            let partial = (0..col)
+                .map(|k| factor[row * dim + k] * factor[col * dim + k])
+                .sum::<f64>();
+            let value = matrix[row * dim + col] as f64 - partial;
+
+            if row == col {
+                if value <= 0.0 {
+                    return None;
+                }
+                factor[row * dim + col] = value.sqrt();
+            } else {
+                factor[row * dim + col] = value / factor[col * dim + col];
+            }
+        }
+    }
+
+    Some(factor.into_iter().map(|value| value as f32).collect())
+}
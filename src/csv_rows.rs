@@ -0,0 +1,33 @@
+use crate::error::{EncoderError, Result};
+use std::str::FromStr;
+
+/// Row-major values and row width of a headerless CSV of floats, one row per non-blank line.
+/// Shared by the centroid loader and the wasm `CentroidAssigner`, so it needs no TensorFlow.
+pub(crate) fn parse_float_rows(contents: &str) -> Result<(Vec<f32>, usize)> {
+    let mut values = Vec::new();
+    let mut width = None;
+
+    for (line_idx, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let row_start = values.len();
+        for value in line.split(',') {
+            let value = f32::from_str(value.trim())
+                .map_err(|e| EncoderError::CentroidParse(format!("Failed to parse centroid row {}: {}", line_idx, e)))?;
+            values.push(value);
+        }
+
+        let row_width = values.len() - row_start;
+        let expected_width = *width.get_or_insert(row_width);
+        if row_width != expected_width {
+            return Err(EncoderError::CentroidParse(format!(
+                "Centroid row {} has {} columns, expected {}",
+                line_idx, row_width, expected_width
+            )));
+        }
+    }
+
+    Ok((values, width.unwrap_or(0)))
+}
@@ -0,0 +1,126 @@
+use crate::backend::saved_model::{resolve_signature, static_width};
+use crate::config::DecoderConfig;
+use crate::encoder::assets_path;
+use crate::error::{EncoderError, Result};
+use crate::session::assignment_session_options;
+use ndarray::Array2;
+use std::path::Path;
+use tensorflow::{Graph, Output, SavedModelBundle, SessionRunArgs, Tensor};
+
+pub(crate) const DECODER_DIR: &str = "vae_decoder";
+
+/// The VAE decoder, mapping latents back to per-bit fingerprint probabilities. Shares the
+/// encoder's assets directory and, like `EncoderModel`, only needs `&self` to run.
+pub struct DecoderModel {
+    bundle: SavedModelBundle,
+    // Kept alive for the operations below, which point into it
+    _graph: Graph,
+    input: Output,
+    output: Output,
+    latent_dim: Option<usize>,
+    output_dim: Option<usize>,
+    config: DecoderConfig,
+}
+
+impl DecoderModel {
+    /// `[rows, bits]` probabilities that each fingerprint bit is set, for `[rows, latent_dim]`
+    /// latents such as those from `EncoderModel::encode_latent`.
+    pub fn decode(&self, latents: &Array2<f32>) -> Result<Array2<f32>> {
+        if latents.nrows() == 0 {
+            return Err(EncoderError::ShapeMismatch("Cannot decode an empty batch".to_string()));
+        }
+        if let Some(latent_dim) = self.latent_dim.filter(|&dim| dim != latents.ncols()) {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Decoder expects latents of width {}, got {}",
+                latent_dim,
+                latents.ncols()
+            )));
+        }
+
+        let flattened_latents = latents.iter().copied().collect::<Vec<f32>>();
+        let latent_tensor = Tensor::new(&[latents.nrows() as u64, latents.ncols() as u64]).with_values(&flattened_latents)?;
+
+        let mut run_args = SessionRunArgs::new();
+        run_args.add_feed(&self.input.operation, self.input.index, &latent_tensor);
+        let output_token = run_args.request_fetch(&self.output.operation, self.output.index);
+        self.bundle.session.run(&mut run_args)?;
+
+        let output_tensor: Tensor<f32> = run_args.fetch(output_token)?;
+        let dims = output_tensor.dims();
+        let mut probabilities = Array2::from_shape_vec((dims[0] as usize, dims[1] as usize), output_tensor.to_vec())?;
+
+        if self.config.logits {
+            probabilities.mapv_inplace(|logit| 1.0 / (1.0 + (-logit).exp()));
+        }
+
+        Ok(probabilities)
+    }
+
+    /// Latent width the decoder's input declares, if its shape is static.
+    pub fn latent_dim(&self) -> Option<usize> {
+        self.latent_dim
+    }
+
+    /// Fingerprint width the decoder's output declares, if its shape is static.
+    pub fn output_dim(&self) -> Option<usize> {
+        self.output_dim
+    }
+
+    pub fn config(&self) -> &DecoderConfig {
+        &self.config
+    }
+}
+
+pub fn build_decoder_model() -> Result<DecoderModel> {
+    build_decoder_model_with_config(DecoderConfig::default())
+}
+
+pub fn build_decoder_model_with_config(config: DecoderConfig) -> Result<DecoderModel> {
+    load_decoder_from_assets(&assets_path()?, config)
+}
+
+/// Loads the decoder from an explicit assets directory.
+pub fn build_decoder_model_from_path(path: &str) -> Result<DecoderModel> {
+    load_decoder_from_assets(path, DecoderConfig::default())
+}
+
+fn load_decoder_from_assets(assets_path: &str, config: DecoderConfig) -> Result<DecoderModel> {
+    let model_dir = Path::new(assets_path).join(&config.decoder_dir);
+    if !model_dir.is_dir() {
+        return Err(EncoderError::MissingAssets(format!(
+            "Missing assets under {}: decoder model {}",
+            assets_path,
+            model_dir.display()
+        )));
+    }
+
+    let session_options = assignment_session_options(&config.session)?;
+    let mut graph = Graph::new();
+    let bundle = SavedModelBundle::load(&session_options, &config.model_tags, &mut graph, model_dir)?;
+
+    let (inputs, output) = resolve_signature(&bundle, &graph, &config.signature_key)?;
+    let input = match inputs.values().next() {
+        Some(input) if inputs.len() == 1 => input.clone(),
+        _ => {
+            return Err(EncoderError::Model(format!(
+                "Decoder signature {} must have exactly one input, found {}",
+                config.signature_key,
+                inputs.len()
+            )))
+        }
+    };
+
+    let latent_dim = static_width(&graph, &input);
+    let output_dim = static_width(&graph, &output);
+    tracing::info!("Loaded decoder model from {}", assets_path);
+
+    Ok(DecoderModel {
+        bundle,
+        _graph: graph,
+        input,
+        output,
+        latent_dim,
+        output_dim,
+        config,
+    })
+}
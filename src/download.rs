@@ -0,0 +1,92 @@
+use crate::archive::{extract_archive, find_assets_root};
+use crate::config::EncoderConfig;
+use crate::encoder::{load_model_from_assets, EncoderModel};
+use crate::error::{EncoderError, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+const CACHE_DIR_NAME: &str = "cheminee-similarity-model";
+
+/// A packed assets archive (`.tar.gz`, `.tgz`, `.tar` or `.zip`) at an HTTP(S) URL, e.g. a public
+/// S3 object, pinned by the SHA-256 of the archive bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetSource {
+    pub url: String,
+    /// Lowercase hex digest
+    pub sha256: String,
+}
+
+/// `$XDG_CACHE_HOME/cheminee-similarity-model`, falling back to `~/.cache`.
+pub fn cache_dir() -> Result<PathBuf> {
+    let cache_home = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(cache_home) => PathBuf::from(cache_home),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".cache"))
+            .ok_or(EncoderError::MissingAssets("Neither XDG_CACHE_HOME nor HOME is set".to_string()))?,
+    };
+
+    Ok(cache_home.join(CACHE_DIR_NAME))
+}
+
+/// Downloads and unpacks `source` into the cache unless an archive with the same checksum was
+/// fetched before, and returns the assets directory inside it.
+pub fn fetch_assets(source: &AssetSource) -> Result<PathBuf> {
+    let cache_dir = cache_dir()?;
+    let assets_dir = cache_dir.join(source.sha256.to_lowercase());
+
+    if !assets_dir.is_dir() {
+        std::fs::create_dir_all(&cache_dir)?;
+
+        tracing::info!("Downloading similarity assets from {}", source.url);
+        let archive_bytes = reqwest::blocking::get(&source.url)
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.bytes())
+            .map_err(|e| EncoderError::Download(format!("Failed to download {}: {}", source.url, e)))?;
+        verify_sha256(&archive_bytes, &source.sha256)?;
+
+        // Unpacked next to its final location and renamed into place, so an interrupted fetch
+        // never leaves a half-written cache entry behind
+        let staging_dir = tempfile::tempdir_in(&cache_dir)?;
+        let archive_path = staging_dir.path().join(archive_file_name(&source.url));
+        std::fs::write(&archive_path, &archive_bytes)?;
+
+        let unpacked_dir = staging_dir.path().join("unpacked");
+        extract_archive(&archive_path, &unpacked_dir)?;
+        if let Err(e) = std::fs::rename(&unpacked_dir, &assets_dir) {
+            // A concurrent fetch of the same archive got there first
+            if !assets_dir.is_dir() {
+                return Err(e.into());
+            }
+        }
+    }
+
+    let encoder_dir = EncoderConfig::default().encoder_dir;
+    find_assets_root(&assets_dir, &encoder_dir)
+}
+
+/// Fetches the assets with `fetch_assets` and loads them like `build_encoder_model_from_path`.
+pub fn build_encoder_model_from_url(source: &AssetSource, config: EncoderConfig) -> Result<EncoderModel> {
+    let assets_path = fetch_assets(source)?;
+    load_model_from_assets(&assets_path.to_string_lossy(), config)
+}
+
+pub fn verify_sha256(bytes: &[u8], expected: &str) -> Result<()> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(EncoderError::ChecksumMismatch {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+// `extract_archive` picks the format from the extension, so keep the URL's file name
+fn archive_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "assets.tar.gz".to_string())
+}
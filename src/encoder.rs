@@ -1,189 +1,1970 @@
-use std::fs::read_to_string;
-use ndarray::Array2;
-use std::str::FromStr;
-use tensorflow::{DataType, Graph, ops, SavedModelBundle, Scope, Session, SessionOptions, SessionRunArgs, Tensor};
+use crate::ann::{top_k_recall, CentroidIndex};
+use crate::backend::{model_asset_path, Encoder, EncoderInput};
+use crate::assignment::rank_native;
+#[cfg(feature = "tensorflow")]
+use crate::assignment::{to_tensor, AssignmentGraphs};
+use crate::archive::{extract_archive, find_assets_root};
+use crate::cancel::CancellationToken;
+#[cfg(feature = "embedded-centroids")]
+use crate::centroids::load_centroids_from_embedded;
+use crate::centroids::{
+    load_centroid_metadata, load_centroids_file, row_major, CentroidSampling, Centroids,
+};
+use crate::config::{ClusterSet, EncoderConfig, NanPolicy, TransformOptions};
+#[cfg(feature = "tensorflow")]
+use crate::config::{AssignmentBackend, SessionConfig};
+use crate::covariance::{load_cluster_covariance, ClusterCovariance};
+use crate::error::{EncoderError, Result};
+use crate::fingerprint::{FingerprintSpec, PackedFingerprints};
+use crate::instrumentation::{self, Stage};
+use crate::kernel::WeightKernel;
+use crate::manifest::{date_stamp, load_asset_manifest};
+use crate::metric::DistanceMetric;
+use crate::npy::NpyWriter;
+use crate::outlier::{outlier_scores, OutlierScores};
+#[cfg(feature = "tensorflow")]
+use crate::{decoder::DecoderModel, outlier::reconstruction_error};
+#[cfg(feature = "tensorflow")]
+use crate::session::assignment_session_options;
+use crate::stats::{
+    jensen_shannon_divergence, normalized_entropy, percentile_ranks, recall_threshold, softmax_over_distances,
+    summarize_distances, BatchRelativeAssignment, ClusterAssignment, DistanceSummary,
+};
+use ndarray::{Array2, Axis};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+#[cfg(feature = "tensorflow")]
+use tensorflow::{DataType, ops, Output, Scope, Session, SessionRunArgs, Tensor};
 
+/// Latent width of the bundled encoder; loaded models are checked against their own declared width
+pub const LATENT_DIM: usize = 128;
+pub(crate) const ENCODER_DIR: &str = "vae_encoder";
+/// Environment variable pointing at an assets directory, checked before the build output scan
+pub const ASSETS_ENV_VAR: &str = "CHEMINEE_SIMILARITY_ASSETS";
+pub(crate) const CENTROIDS_FILE: &str = "lf_kmeans_10k_centroids_20241111.csv";
+const CANCELLABLE_CHUNK_SIZE: usize = 256;
+const WARM_UP_BATCH_SIZE: usize = 8;
+// Fingerprint width of the bundled encoder, for backends that can't report their input width
+const BUNDLED_INPUT_DIM: usize = 2048;
+
+/// Safe to share behind an `Arc`. Encoding and assignment only need `&self`, and every backend
+/// (TensorFlow, ONNX Runtime, tract, candle) accepts concurrent runs on one session, so parallel
+/// `transform` calls don't wait on each other; usage counts are atomic. They do share the
+/// session's intra-op thread pool, which `EncoderPool` avoids by giving each model its own.
 pub struct EncoderModel {
-    encoder: SavedModelBundle,
-    graph: Graph,
+    encoder: Encoder,
+    centroids: Centroids,
+    sampled_centroids: Option<(Array2<f32>, Vec<i32>)>,
+    // `None` with the native assignment backend
+    #[cfg(feature = "tensorflow")]
+    assignment_graphs: Option<AssignmentGraphs>,
+    centroid_index: Option<CentroidIndex>,
+    coarse_centroids: Option<CoarseCentroids>,
+    // Aligned with `centroids` by cluster id, for `DistanceMetric::Mahalanobis`
+    cluster_covariance: Option<ClusterCovariance>,
+    config: EncoderConfig,
+    usage_counts: Vec<AtomicU64>,
+    reference_latents: Option<Array2<f32>>,
+    model_version: Option<String>,
+    centroid_version: Option<String>,
+    fingerprint_spec: Option<FingerprintSpec>,
+}
+
+// The `ClusterSet::Coarse` partition, assigned with the same backend and metric as the main one
+struct CoarseCentroids {
+    centroids: Centroids,
+    // `None` with the native assignment backend
+    #[cfg(feature = "tensorflow")]
+    assignment_graphs: Option<AssignmentGraphs>,
 }
 
-lazy_static::lazy_static! {
-    static ref ASSETS_PATH: String = get_assets_path().unwrap();
-    static ref CENTROIDS: Tensor<f32> = load_cluster_centroids().unwrap();
-    pub static ref NUM_CLUSTERS: f32 = CENTROIDS.dims()[0] as f32;
-}
+impl CoarseCentroids {
+    fn rank(&self, lf_array: &Array2<f32>, metric: DistanceMetric) -> Result<Vec<Vec<(i32, f32)>>> {
+        #[cfg(feature = "tensorflow")]
+        if let Some(assignment_graphs) = &self.assignment_graphs {
+            return assignment_graphs.rank(lf_array, None);
+        }
+
+        Ok(rank_native(lf_array, &self.centroids.coordinates, metric, None))
+    }
+}
+
+impl EncoderModel {
+    pub fn transform(&self, input_data: &[Vec<i64>]) -> Result<Vec<Vec<i32>>> {
+        let ranked_clusters = self.rank_top_clusters(input_data, self.config.top_n)?;
+        self.record_usage(&ranked_clusters);
+        Ok(ranked_labels(ranked_clusters))
+    }
+
+    /// Like `transform`, but takes bit-packed rows, which are unpacked straight into the encoder's
+    /// input tensor instead of through one `i64` per bit.
+    pub fn transform_packed(&self, fingerprints: &PackedFingerprints) -> Result<Vec<Vec<i32>>> {
+        let lf_array = self.encode_packed(fingerprints)?;
+        let ranked_clusters = self.rank_latents(lf_array, self.config.top_n)?;
+        self.record_usage(&ranked_clusters);
+        Ok(ranked_labels(ranked_clusters))
+    }
+
+    /// Like `transform`, but runs in chunks of rows and checks `token` between them, returning an
+    /// error as soon as cancellation is seen. Rows already assigned are discarded.
+    pub fn transform_cancellable(&self, input_data: &[Vec<i64>], token: CancellationToken) -> Result<Vec<Vec<i32>>> {
+        let mut ranked_cluster_labels = Vec::with_capacity(input_data.len());
+
+        for chunk in input_data.chunks(CANCELLABLE_CHUNK_SIZE) {
+            if token.is_cancelled() {
+                return Err(EncoderError::Cancelled {
+                    assigned: ranked_cluster_labels.len(),
+                    total: input_data.len(),
+                });
+            }
+
+            ranked_cluster_labels.extend(self.transform(chunk)?);
+        }
+
+        Ok(ranked_cluster_labels)
+    }
+
+    /// Lazily transforms `rows` `chunk_size` at a time, yielding one result per chunk so only a
+    /// single chunk of fingerprints and labels is held in memory. Pulling past an error continues
+    /// with the next chunk.
+    pub fn transform_iter<'a, I>(
+        &'a self,
+        rows: I,
+        chunk_size: usize,
+    ) -> impl Iterator<Item = Result<Vec<Vec<i32>>>> + 'a
+    where
+        I: IntoIterator<Item = Vec<i64>>,
+        I::IntoIter: 'a,
+    {
+        let chunk_size = chunk_size.max(1);
+        let mut rows = rows.into_iter();
+
+        std::iter::from_fn(move || {
+            let chunk = rows.by_ref().take(chunk_size).collect::<Vec<Vec<i64>>>();
+            if chunk.is_empty() {
+                return None;
+            }
+
+            Some(self.transform(&chunk))
+        })
+    }
+
+    /// Ranked cluster labels for models whose signature takes several named inputs, e.g. a
+    /// fingerprint plus a descriptor vector. Every signature input must be supplied, with the same
+    /// number of rows; values are cast to each input's dtype.
+    #[cfg(feature = "tensorflow")]
+    pub fn transform_multi_input(&self, named_inputs: &HashMap<String, Array2<f32>>) -> Result<Vec<Vec<i32>>> {
+        let lf_array = self.encode_named(named_inputs)?;
+        let ranked_clusters = self.rank_latents(lf_array, self.config.top_n)?;
+        self.record_usage(&ranked_clusters);
+        Ok(ranked_labels(ranked_clusters))
+    }
+
+    /// Ranked `(label, distance)` pairs per row, nearest first, using the configured metric.
+    pub fn transform_with_distances(&self, input_data: &[Vec<i64>]) -> Result<Vec<Vec<(i32, f32)>>> {
+        let ranked_clusters = self.rank_top_clusters(input_data, self.config.top_n)?;
+        self.record_usage(&ranked_clusters);
+        Ok(ranked_clusters)
+    }
+
+    /// Soft assignment: `softmax(-distance / temperature)` over all ranked clusters, as
+    /// `(label, probability)` pairs nearest first. Without an explicit temperature the exponential
+    /// kernel's (possibly calibrated) temperature is used, or `1.0` for other kernels.
+    pub fn transform_probabilities(&self, input_data: &[Vec<i64>], temperature: Option<f32>) -> Result<Vec<Vec<(i32, f32)>>> {
+        let temperature = temperature.unwrap_or(match self.config.kernel {
+            WeightKernel::Exponential { temperature } => temperature,
+            _ => 1.0,
+        });
+
+        if temperature <= 0.0 {
+            return Err(EncoderError::InvalidArgument(format!("Temperature must be positive, got {}", temperature)));
+        }
+
+        let ranked_clusters = self.rank_clusters(input_data)?;
+
+        let probabilities = ranked_clusters
+            .into_iter()
+            .map(|row| {
+                let distances = row.iter().map(|(_, distance)| *distance).collect::<Vec<f32>>();
+
+                row.iter()
+                    .zip(softmax_over_distances(&distances, temperature))
+                    .map(|((label, _), probability)| (*label, probability as f32))
+                    .collect()
+            })
+            .collect::<Vec<Vec<(i32, f32)>>>();
+
+        Ok(probabilities)
+    }
+
+    /// Ranked cluster labels against the chosen centroid set, cut to `top_n`. `ClusterSet::Fine` is
+    /// `transform`; `ClusterSet::Coarse` needs `coarse_centroids_file` and does not count towards
+    /// usage, which is kept per fine cluster.
+    pub fn transform_with(&self, input_data: &[Vec<i64>], cluster_set: ClusterSet) -> Result<Vec<Vec<i32>>> {
+        let coarse = match cluster_set {
+            ClusterSet::Fine => return self.transform(input_data),
+            ClusterSet::Coarse => self
+                .coarse_centroids
+                .as_ref()
+                .ok_or(EncoderError::NotConfigured("No coarse centroid set configured".to_string()))?,
+        };
+
+        let lf_array = self.encode(input_data)?;
+        let mut ranked_clusters =
+            self.rank_assignable_latents(lf_array, None, |latents| coarse.rank(latents, self.config.metric))?;
+        if let Some(top_n) = self.config.top_n {
+            ranked_clusters.iter_mut().for_each(|row| row.truncate(top_n));
+        }
+
+        Ok(ranked_labels(ranked_clusters))
+    }
+
+    /// Like `transform`, but ranks only the nearest `n` clusters per row instead of all of them.
+    pub fn transform_top_n(&self, input_data: &[Vec<i64>], n: usize) -> Result<Vec<Vec<i32>>> {
+        let ranked_clusters = self.rank_top_clusters(input_data, Some(n))?;
+        self.record_usage(&ranked_clusters);
+
+        let ranked_cluster_labels = ranked_clusters
+            .into_iter()
+            .map(|row| row.into_iter().take(n).map(|(label, _)| label).collect())
+            .collect::<Vec<Vec<i32>>>();
+
+        Ok(ranked_cluster_labels)
+    }
+
+    /// Like `transform_top_n`, but returns serializable per-row assignments with their distances.
+    pub fn transform_assignments(&self, input_data: &[Vec<i64>], n: usize) -> Result<Vec<ClusterAssignment>> {
+        let ranked_clusters = self.rank_top_clusters(input_data, Some(n))?;
+        self.record_usage(&ranked_clusters);
+
+        let assignments = ranked_clusters
+            .into_iter()
+            .map(|row| row.into_iter().take(n).collect::<Vec<(i32, f32)>>().into())
+            .collect::<Vec<ClusterAssignment>>();
+
+        Ok(assignments)
+    }
+
+    /// Like `transform`, but keeps only the top `ks[i]` clusters for row `i`.
+    pub fn transform_variable_k(&self, input_data: &[Vec<i64>], ks: &[usize]) -> Result<Vec<Vec<i32>>> {
+        if ks.len() != input_data.len() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} top-k values for {} input rows",
+                ks.len(),
+                input_data.len()
+            )));
+        }
+
+        let ranked_clusters = self.rank_top_clusters(input_data, ks.iter().copied().max())?;
+        self.record_usage(&ranked_clusters);
+
+        let mut ranked_cluster_labels = ranked_labels(ranked_clusters);
+        for (row, &k) in ranked_cluster_labels.iter_mut().zip(ks) {
+            row.truncate(k);
+        }
+
+        Ok(ranked_cluster_labels)
+    }
+
+    /// Returns `(label, weight)` pairs per row, ordered by descending kernel weight.
+    ///
+    /// For monotonically decreasing kernels (all built-in variants) this is the same order as
+    /// `transform`; custom kernels that peak away from zero distance may reorder clusters.
+    pub fn transform_by_weight(&self, input_data: &[Vec<i64>]) -> Result<Vec<Vec<(i32, f32)>>> {
+        let ranked_clusters = self.rank_clusters(input_data)?;
+
+        let weighted_clusters = ranked_clusters
+            .into_iter()
+            .map(|row| {
+                let mut weighted_row = row
+                    .into_iter()
+                    .map(|(label, distance)| (label, self.weight(distance)))
+                    .collect::<Vec<(i32, f32)>>();
+
+                weighted_row.sort_by(|a, b| b.1.total_cmp(&a.1));
+                weighted_row
+            })
+            .collect::<Vec<Vec<(i32, f32)>>>();
+
+        Ok(weighted_clusters)
+    }
+
+    /// Ranked `(label, weight)` pairs per row, with `options` overriding the configured top-k,
+    /// metric, kernel and clamp for this call only.
+    pub fn transform_with_options(&self, input_data: &[Vec<i64>], options: &TransformOptions) -> Result<Vec<Vec<(i32, f32)>>> {
+        let metric = options.metric.unwrap_or(self.config.metric);
+        let kernel = options.kernel.as_ref().unwrap_or(&self.config.kernel);
+        let weight_clamp = options.weight_clamp.as_ref().unwrap_or(&self.config.weight_clamp);
+        let top_k = options.top_k.unwrap_or(usize::MAX);
+
+        let ranked_clusters = if metric == self.config.metric {
+            self.rank_clusters(input_data)?
+        } else {
+            self.rank_clusters_with_metric(input_data, metric)?
+        };
+
+        let weighted_clusters = ranked_clusters
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .take(top_k)
+                    .map(|(label, distance)| (label, weight_clamp.apply(kernel.weight(distance))))
+                    .collect()
+            })
+            .collect::<Vec<Vec<(i32, f32)>>>();
+
+        Ok(weighted_clusters)
+    }
+
+    /// Top `k` `(label, distance, weight)` triples per row, nearest first, from a single encoder run.
+    pub fn transform_detailed(&self, input_data: &[Vec<i64>], k: usize) -> Result<Vec<Vec<(i32, f32, f32)>>> {
+        let ranked_clusters = self.rank_top_clusters(input_data, Some(k))?;
+
+        let detailed_clusters = ranked_clusters
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .take(k)
+                    .map(|(label, distance)| (label, distance, self.weight(distance)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<(i32, f32, f32)>>>();
+
+        Ok(detailed_clusters)
+    }
+
+    fn weight(&self, distance: f32) -> f32 {
+        self.config.weight_clamp.apply(self.config.kernel.weight(distance))
+    }
+
+    /// Weights the top `k` clusters per row by rank alone: `decay^rank`, so the nearest gets `1.0`.
+    pub fn rank_weights(&self, input_data: &[Vec<i64>], k: usize, decay: f32) -> Result<Vec<Vec<(i32, f32)>>> {
+        if !(decay > 0.0 && decay <= 1.0) {
+            return Err(EncoderError::InvalidArgument(format!("Rank decay must be in (0, 1], got {}", decay)));
+        }
+
+        let ranked_cluster_labels = ranked_labels(self.rank_top_clusters(input_data, Some(k))?);
+
+        let weighted_clusters = ranked_cluster_labels
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .take(k)
+                    .enumerate()
+                    .map(|(rank, label)| (label, self.config.weight_clamp.apply(decay.powi(rank as i32))))
+                    .collect()
+            })
+            .collect::<Vec<Vec<(i32, f32)>>>();
+
+        Ok(weighted_clusters)
+    }
+
+    /// Distance to the nearest centroid per row; `NaN` for rows that could not be assigned.
+    pub fn nearest_distance(&self, input_data: &[Vec<i64>]) -> Result<Vec<f32>> {
+        let ranked_clusters = self.rank_clusters(input_data)?;
+
+        let nearest_distances = ranked_clusters
+            .iter()
+            .map(|row| row.first().map(|(_, distance)| *distance).unwrap_or(f32::NAN))
+            .collect();
+
+        Ok(nearest_distances)
+    }
+
+    /// Nearest cluster per row with its distance expressed as a percentile within this batch, for
+    /// batch-adaptive outlier cut-offs. `None` for rows that could not be assigned.
+    pub fn transform_batch_relative(&self, input_data: &[Vec<i64>]) -> Result<Vec<Option<BatchRelativeAssignment>>> {
+        let ranked_clusters = self.rank_clusters(input_data)?;
+
+        let nearest_clusters = ranked_clusters
+            .iter()
+            .map(|row| row.first().copied())
+            .collect::<Vec<Option<(i32, f32)>>>();
+
+        let nearest_distances = nearest_clusters
+            .iter()
+            .map(|nearest| nearest.map(|(_, distance)| distance).unwrap_or(f32::NAN))
+            .collect::<Vec<f32>>();
+
+        let assignments = nearest_clusters
+            .into_iter()
+            .zip(percentile_ranks(&nearest_distances))
+            .map(|(nearest, distance_percentile)| {
+                nearest.map(|(cluster_id, distance)| BatchRelativeAssignment {
+                    cluster_id,
+                    distance,
+                    distance_percentile,
+                })
+            })
+            .collect();
+
+        Ok(assignments)
+    }
+
+    pub fn batch_distance_summary(&self, input_data: &[Vec<i64>]) -> Result<DistanceSummary> {
+        let nearest_distances = self.nearest_distance(input_data)?;
+        summarize_distances(&nearest_distances)
+    }
+
+    /// Number of distinct top-1 clusters in the batch and the normalized entropy of the top-1
+    /// distribution. The entropy is relative to the most even spread the batch could achieve, so a
+    /// batch of `n` rows landing in `n` different clusters scores `1.0`.
+    pub fn effective_clusters(&self, input_data: &[Vec<i64>]) -> Result<(usize, f32)> {
+        let ranked_cluster_labels = ranked_labels(self.rank_top_clusters(input_data, Some(1))?);
+
+        let mut top1_counts: HashMap<i32, u64> = HashMap::new();
+        for &label in ranked_cluster_labels.iter().filter_map(|row| row.first()) {
+            *top1_counts.entry(label).or_insert(0) += 1;
+        }
+
+        let assigned_rows = top1_counts.values().sum::<u64>() as usize;
+        let max_categories = assigned_rows.min(self.centroids.num_clusters());
+        let counts = top1_counts.into_values().collect::<Vec<u64>>();
+
+        Ok((counts.len(), normalized_entropy(&counts, max_categories)))
+    }
+
+    /// Smallest distance threshold at which at least `target_recall` of `inputs` lie within it of
+    /// their `expected` cluster's centroid, e.g. to pick `max_nearest_distance` from data.
+    pub fn calibrate_threshold(&self, inputs: &[Vec<i64>], expected: &[i32], target_recall: f64) -> Result<f32> {
+        if inputs.len() != expected.len() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} expected clusters for {} inputs",
+                expected.len(),
+                inputs.len()
+            )));
+        }
+
+        let ranked_clusters = self.rank_clusters(inputs)?;
+
+        // Rows whose expected cluster was not ranked count as misses
+        let expected_distances = ranked_clusters
+            .iter()
+            .zip(expected)
+            .map(|(row, &expected_label)| {
+                row.iter()
+                    .find(|(label, _)| *label == expected_label)
+                    .map(|(_, distance)| *distance)
+                    .unwrap_or(f32::NAN)
+            })
+            .collect::<Vec<f32>>();
+
+        recall_threshold(&expected_distances, target_recall)
+    }
+
+    /// Inclusive `[start, end]` cluster id ranges covering every cluster in the top `k` of any row,
+    /// with adjacent and overlapping ids merged so each range maps to one scan of a sorted store.
+    pub fn batch_cluster_ranges(&self, input_data: &[Vec<i64>], k: usize) -> Result<Vec<(i32, i32)>> {
+        let ranked_cluster_labels = ranked_labels(self.rank_top_clusters(input_data, Some(k))?);
+        let touched_clusters = ranked_cluster_labels
+            .into_iter()
+            .flat_map(|row| row.into_iter().take(k));
+
+        Ok(cluster_ranges(touched_clusters))
+    }
+
+    /// Top `k` `(label, distance)` pairs for the mean latent of the batch, as a single
+    /// representative routing for the group. Rows skipped by the NaN policy are left out of the mean.
+    pub fn batch_centroid_assignment(&self, input_data: &[Vec<i64>], k: usize) -> Result<Vec<(i32, f32)>> {
+        let mut lf_array = self.encode(input_data)?;
+        let skipped_rows = self.apply_nan_policy(&mut lf_array)?;
+        let cols = lf_array.ncols();
+
+        let mut mean_latent = vec![0.0f32; cols];
+        let mut num_rows = 0;
+        for (_, row_vec) in lf_array
+            .rows()
+            .into_iter()
+            .enumerate()
+            .filter(|(row_idx, _)| !skipped_rows.contains(row_idx))
+        {
+            mean_latent.iter_mut().zip(row_vec).for_each(|(mean, value)| *mean += value);
+            num_rows += 1;
+        }
+
+        if num_rows == 0 {
+            return Err(EncoderError::ShapeMismatch("No rows left to average".to_string()));
+        }
+        mean_latent.iter_mut().for_each(|mean| *mean /= num_rows as f32);
+
+        let mean_latent = Array2::from_shape_vec((1, cols), mean_latent)?;
+        let mut ranked_clusters = self.rank_latents(mean_latent, Some(k))?;
+
+        let mut batch_clusters = ranked_clusters.remove(0);
+        batch_clusters.truncate(k);
+
+        Ok(batch_clusters)
+    }
+
+    /// Counts `(true_label, predicted_top1_label)` pairs over a labeled dataset. Rows that could
+    /// not be assigned are left out.
+    pub fn assignment_confusion(&self, inputs: &[Vec<i64>], true_labels: &[i32]) -> Result<HashMap<(i32, i32), u64>> {
+        if inputs.len() != true_labels.len() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} true labels for {} inputs",
+                true_labels.len(),
+                inputs.len()
+            )));
+        }
+
+        let ranked_cluster_labels = ranked_labels(self.rank_top_clusters(inputs, Some(1))?);
+
+        let mut confusion = HashMap::new();
+        for (row, &true_label) in ranked_cluster_labels.iter().zip(true_labels) {
+            if let Some(&predicted_label) = row.first() {
+                *confusion.entry((true_label, predicted_label)).or_insert(0) += 1;
+            }
+        }
+
+        Ok(confusion)
+    }
+
+    /// Runs assignment twice on the same input and reports whether every row got the same top-1
+    /// label both times. Meant as a startup or CI gate against nondeterministic TF builds.
+    pub fn self_consistency_check(&self, input: &[Vec<i64>]) -> Result<bool> {
+        let inconsistent_rows = self.inconsistent_rows(input)?;
+
+        if !inconsistent_rows.is_empty() {
+            tracing::info!("Top-1 assignments differed between runs for rows {:?}", inconsistent_rows);
+        }
+
+        Ok(inconsistent_rows.is_empty())
+    }
+
+    /// Indices of rows whose top-1 label differs between two assignment runs on the same input.
+    pub fn inconsistent_rows(&self, input: &[Vec<i64>]) -> Result<Vec<usize>> {
+        let first_run = self.rank_clusters(input)?;
+        let second_run = self.rank_clusters(input)?;
+
+        let inconsistent_rows = first_run
+            .iter()
+            .zip(&second_run)
+            .enumerate()
+            .filter(|(_, (first, second))| {
+                first.first().map(|(label, _)| label) != second.first().map(|(label, _)| label)
+            })
+            .map(|(row_idx, _)| row_idx)
+            .collect();
+
+        Ok(inconsistent_rows)
+    }
+
+    /// 1-based position of `cluster_id` in the full distance ranking for `input`, so the nearest
+    /// cluster has rank 1 and `1.0 / rank` is its reciprocal rank.
+    pub fn cluster_rank(&self, input: &[i64], cluster_id: i32) -> Result<usize> {
+        let num_clusters = self.centroids.num_clusters();
+        if cluster_id < 0 || cluster_id as usize >= num_clusters {
+            return Err(EncoderError::InvalidArgument(format!(
+                "Cluster id {} is out of range for {} clusters",
+                cluster_id,
+                num_clusters
+            )));
+        }
+
+        let ranked_clusters = self.rank_clusters(&[input.to_vec()])?;
+
+        ranked_clusters[0]
+            .iter()
+            .position(|(label, _)| *label == cluster_id)
+            .map(|position| position + 1)
+            .ok_or(EncoderError::InvalidArgument(format!("Cluster {} does not appear in the ranking", cluster_id)))
+    }
+
+    pub fn is_outlier(&self, input: &[i64]) -> Result<bool> {
+        let (is_outlier, _) = self.outlier_scores(input)?;
+        Ok(is_outlier)
+    }
+
+    /// Same as `is_outlier`, but also returns the signals the decision was based on.
+    pub fn outlier_scores(&self, input: &[i64]) -> Result<(bool, OutlierScores)> {
+        let ranked_clusters = self.rank_clusters(&[input.to_vec()])?;
+        let scores = outlier_scores(&ranked_clusters[0])?;
+        let is_outlier = self.config.outlier_thresholds.is_outlier(&scores);
+
+        Ok((is_outlier, scores))
+    }
+
+    /// Same as `outlier_scores`, plus how badly `decoder` reconstructs `input` from its latent, so
+    /// `max_reconstruction_error` applies as well.
+    #[cfg(feature = "tensorflow")]
+    pub fn outlier_scores_with_decoder(&self, input: &[i64], decoder: &DecoderModel) -> Result<(bool, OutlierScores)> {
+        let (lf_array, ranked_clusters) = self.rank_top_clusters_with_latents(&[input.to_vec()], None)?;
+        let probabilities = decoder.decode(&lf_array)?;
+
+        let mut scores = outlier_scores(&ranked_clusters[0])?;
+        scores.reconstruction_error = Some(reconstruction_error(input, &row_major(&probabilities))?);
+        let is_outlier = self.config.outlier_thresholds.is_outlier(&scores);
+
+        Ok((is_outlier, scores))
+    }
+
+    pub fn latent_distance(&self, a: &[i64], b: &[i64]) -> Result<f32> {
+        if a.len() != b.len() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Fingerprint lengths differ: {} vs {}",
+                a.len(),
+                b.len()
+            )));
+        }
+
+        let lf_array = self.encode(&[a.to_vec(), b.to_vec()])?;
+        let latents = row_major(&lf_array);
+        let (lf_a, lf_b) = latents.split_at(lf_array.ncols());
+
+        Ok(self.config.metric.distance(lf_a, lf_b))
+    }
+
+    /// Raw encoder latents, one `[dim]` row per fingerprint. No NaN policy is applied.
+    pub fn encode_latent(&self, input_data: &[Vec<i64>]) -> Result<Array2<f32>> {
+        self.encode(input_data)
+    }
+
+    /// Ranked `(label, distance)` pairs for precomputed latents, e.g. from `encode_latent`, with the
+    /// configured assignment backend and metric and the NaN policy applied. Keeps the nearest
+    /// `top_n` clusters per row, or all of them.
+    pub fn assign_latents(&self, latents: &Array2<f32>, top_n: Option<usize>) -> Result<Vec<Vec<(i32, f32)>>> {
+        let lf_array = latents.as_standard_layout().into_owned();
+        self.check_latent_width(&lf_array)?;
+
+        self.rank_latents(lf_array, top_n)
+    }
+
+    /// Encodes `input` in batches of `batch_size` and streams the latents into a `.npy` file of
+    /// shape `[rows, dim]`, returning the number of rows written.
+    pub fn encode_latents_to_file(
+        &self,
+        input: impl Iterator<Item = Vec<i64>>,
+        path: &str,
+        batch_size: usize,
+    ) -> Result<usize> {
+        let batch_size = batch_size.max(1);
+        let mut writer = NpyWriter::create(path, self.centroids.dim())?;
+        let mut batch = Vec::with_capacity(batch_size);
+
+        for fingerprint in input {
+            batch.push(fingerprint);
+
+            if batch.len() == batch_size {
+                writer.write_rows(&row_major(&self.encode(&batch)?))?;
+                batch.clear();
+            }
+        }
+
+        if !batch.is_empty() {
+            writer.write_rows(&row_major(&self.encode(&batch)?))?;
+        }
+
+        writer.finish()
+    }
+
+    /// Jensen-Shannon divergence between the `softmax(-distance / temperature)` cluster
+    /// distributions of two fingerprints; `0` when they route identically, at most `ln 2`.
+    pub fn cluster_distribution_divergence(&self, a: &[i64], b: &[i64], temperature: f32) -> Result<f32> {
+        if temperature <= 0.0 {
+            return Err(EncoderError::InvalidArgument(format!("Temperature must be positive, got {}", temperature)));
+        }
+
+        let mut ranked_clusters = self.rank_clusters(&[a.to_vec(), b.to_vec()])?;
+
+        // Line both rows up by cluster id so the distributions share an outcome order
+        for row in ranked_clusters.iter_mut() {
+            if row.is_empty() {
+                return Err(EncoderError::ShapeMismatch("Could not assign clusters to both fingerprints".to_string()));
+            }
+            row.sort_by_key(|(label, _)| *label);
+        }
+
+        let distributions = ranked_clusters
+            .iter()
+            .map(|row| {
+                let distances = row.iter().map(|(_, distance)| *distance).collect::<Vec<f32>>();
+                softmax_over_distances(&distances, temperature)
+            })
+            .collect::<Vec<Vec<f64>>>();
+
+        if distributions[0].len() != distributions[1].len() {
+            return Err(EncoderError::InvalidArgument("Fingerprints were ranked against different cluster sets".to_string()));
+        }
+
+        Ok(jensen_shannon_divergence(&distributions[0], &distributions[1]))
+    }
+
+    /// Returns an `[|A|, |B|]` matrix of latent similarities, `1 / (1 + distance)` under the
+    /// configured `metric`.
+    #[cfg(feature = "tensorflow")]
+    pub fn similarity_matrix(&self, set_a: &[Vec<i64>], set_b: &[Vec<i64>]) -> Result<Array2<f32>> {
+        let lf_a = self.encode(set_a)?;
+        let lf_b = self.encode(set_b)?;
+
+        let distances = pairwise_distances(&lf_a, &lf_b, metric_distance_fn(self.config.metric), &self.config.session)?;
+        let similarities = distances.iter().map(|distance| 1.0 / (1.0 + distance)).collect();
+
+        let matrix = Array2::from_shape_vec((set_a.len(), set_b.len()), similarities)?;
+        Ok(matrix)
+    }
+
+    /// Top `k` `(label, distance)` pairs per row from the HNSW centroid index. Requires `hnsw`
+    /// to be set in the config; exact `transform` remains the default path.
+    pub fn transform_approximate(&self, input_data: &[Vec<i64>], k: usize) -> Result<Vec<Vec<(i32, f32)>>> {
+        let (lf_array, ranked_clusters) = self.search_centroid_index(input_data, k)?;
+
+        let check_rows = self
+            .config
+            .hnsw
+            .as_ref()
+            .map_or(0, |hnsw_config| hnsw_config.exactness_check_rows)
+            .min(ranked_clusters.len());
+        if check_rows > 0 {
+            let recall = self.centroid_index_recall(&lf_array, &ranked_clusters[..check_rows], k);
+            tracing::info!("HNSW recall@{} over {} rows: {:.4}", k, check_rows, recall);
+        }
+
+        Ok(ranked_clusters)
+    }
+
+    /// Recall@`k` of the HNSW centroid index against exact ranking over all centroids: the
+    /// fraction of each row's exact top `k` labels that the index also returns, averaged over rows.
+    pub fn hnsw_recall(&self, input_data: &[Vec<i64>], k: usize) -> Result<f32> {
+        let (lf_array, ranked_clusters) = self.search_centroid_index(input_data, k)?;
+        Ok(self.centroid_index_recall(&lf_array, &ranked_clusters, k))
+    }
+
+    fn search_centroid_index(&self, input_data: &[Vec<i64>], k: usize) -> Result<(Array2<f32>, Vec<Vec<(i32, f32)>>)> {
+        let centroid_index = self
+            .centroid_index
+            .as_ref()
+            .ok_or(EncoderError::NotConfigured("No HNSW centroid index configured".to_string()))?;
+
+        let mut lf_array = self.encode(input_data)?;
+        let skipped_rows = self.apply_nan_policy(&mut lf_array)?;
+        let cols = lf_array.ncols();
+
+        let ranked_clusters = row_major(&lf_array)
+            .chunks(cols)
+            .enumerate()
+            .map(|(row_idx, row_vec)| {
+                if skipped_rows.contains(&row_idx) {
+                    vec![]
+                } else {
+                    centroid_index.search(row_vec, k)
+                }
+            })
+            .collect();
+
+        Ok((lf_array, ranked_clusters))
+    }
+
+    // Exact ranks the leading `approximate.len()` latent rows and compares the label sets
+    fn centroid_index_recall(&self, lf_array: &Array2<f32>, approximate: &[Vec<(i32, f32)>], k: usize) -> f32 {
+        let cols = lf_array.ncols();
+        let exact = self.config.metric.rank(
+            &row_major(lf_array)[..approximate.len() * cols],
+            &row_major(&self.centroids.coordinates),
+            cols,
+            Some(k),
+        );
+
+        top_k_recall(approximate, &exact)
+    }
+
+    /// Writes the cluster-assignment graph as a serialized `GraphDef` with the centroids frozen as
+    /// constants, measuring distance under the configured metric. Feed `[batch, dim]` latents to
+    /// `latent`; fetch the `[batch, num_clusters]` `cluster_labels` and `distances`.
+    ///
+    /// The graph always ranks the full centroid set. Full-covariance Mahalanobis has no graph form
+    /// and is rejected.
+    #[cfg(feature = "tensorflow")]
+    pub fn export_assignment_graph(&self, path: &str) -> Result<()> {
+        let centroids = &self.centroids.coordinates;
+
+        let graph_def = match self.cluster_covariance.as_ref().filter(|_| self.config.metric == DistanceMetric::Mahalanobis) {
+            Some(covariance) => {
+                let Some(inverse_variances) = covariance.diagonal_rows(None) else {
+                    return Err(EncoderError::InvalidArgument(
+                        "Full-covariance Mahalanobis distance can't be exported as an assignment graph".to_string(),
+                    ));
+                };
+
+                let inverse_variances = Array2::from_shape_vec(centroids.dim(), inverse_variances)?;
+                assignment_graph_def(centroids, &|scope: &mut Scope, centroids_input: Output, lf_input: Output| {
+                    diagonal_mahalanobis_distance(scope, centroids_input, lf_input, &inverse_variances)
+                })?
+            }
+            None => assignment_graph_def(centroids, &metric_distance_fn(self.config.metric))?,
+        };
+        std::fs::write(path, graph_def)?;
+
+        Ok(())
+    }
+
+    /// Ranks clusters using a caller-built distance graph instead of the configured metric, cut to
+    /// `top_n`.
+    ///
+    /// `distance_fn` receives the `[num_clusters, dim]` centroid constant and `[batch, dim]` latent
+    /// placeholder and must return a `[batch, num_clusters]` output where smaller values mean
+    /// closer clusters.
+    #[cfg(feature = "tensorflow")]
+    pub fn transform_with_distance_fn<F>(&self, input_data: &[Vec<i64>], distance_fn: F) -> Result<Vec<Vec<i32>>>
+    where
+        F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+    {
+        let mut ranked_clusters = self.rank_clusters_with(input_data, &distance_fn)?;
+        if let Some(top_n) = self.config.top_n {
+            ranked_clusters.iter_mut().for_each(|row| row.truncate(top_n));
+        }
+        self.record_usage(&ranked_clusters);
+
+        Ok(ranked_labels(ranked_clusters))
+    }
+
+    /// Finds the pool fingerprint whose latent lies closest to centroid `cluster_id`, returning its
+    /// index in `pool` and its latent vector. `None` for an empty pool.
+    pub fn centroid_nearest_input(&self, cluster_id: i32, pool: &[Vec<i64>]) -> Result<Option<(usize, Vec<f32>)>> {
+        let num_clusters = self.centroids.num_clusters();
+        if cluster_id < 0 || cluster_id as usize >= num_clusters {
+            return Err(EncoderError::InvalidArgument(format!(
+                "Cluster id {} is out of range for {} clusters",
+                cluster_id,
+                num_clusters
+            )));
+        }
+
+        if pool.is_empty() {
+            return Ok(None);
+        }
+
+        let dim = self.centroids.dim();
+        let centroid = self.centroids.coordinates.row(cluster_id as usize).to_vec();
+
+        let lf_array = self.encode(pool)?;
+        let nearest_input = row_major(&lf_array)
+            .chunks(dim)
+            .enumerate()
+            .map(|(idx, latent)| (idx, self.config.metric.distance(&centroid, latent), latent))
+            .filter(|(_, distance, _)| !distance.is_nan())
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _, latent)| (idx, latent.to_vec()));
+
+        Ok(nearest_input)
+    }
+
+    /// Fraction of `latents` whose nearest centroid is the same cluster id under the model's
+    /// centroids and under `other`, e.g. a regenerated centroid set.
+    pub fn centroid_set_agreement(&self, latents: &[Vec<f32>], other: &Array2<f32>) -> Result<f64> {
+        let dim = self.centroids.dim();
+        if other.ncols() != dim {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Other centroid set has dimension {}, expected {}",
+                other.ncols(),
+                dim
+            )));
+        }
+
+        if let Some(latent) = latents.iter().find(|latent| latent.len() != dim) {
+            return Err(EncoderError::ShapeMismatch(format!("Latent has dimension {}, expected {}", latent.len(), dim)));
+        }
+
+        if latents.is_empty() {
+            return Err(EncoderError::ShapeMismatch("No latents to compare".to_string()));
+        }
+
+        let other = other.as_standard_layout();
+        let other_rows = other
+            .as_slice()
+            .ok_or(EncoderError::ShapeMismatch("Failed to convert array to slice".to_string()))?;
+
+        let metric = self.config.metric;
+        let coordinates = row_major(&self.centroids.coordinates);
+        let agreements = latents
+            .iter()
+            .filter(|latent| {
+                let current = metric.nearest(coordinates.chunks(dim), latent);
+                let candidate = metric.nearest(other_rows.chunks(dim), latent);
+
+                matches!((current, candidate), (Some((a, _)), Some((b, _))) if a == b)
+            })
+            .count();
+
+        Ok(agreements as f64 / latents.len() as f64)
+    }
+
+    fn rank_clusters(&self, input_data: &[Vec<i64>]) -> Result<Vec<Vec<(i32, f32)>>> {
+        self.rank_top_clusters(input_data, None)
+    }
+
+    pub(crate) fn rank_top_clusters(&self, input_data: &[Vec<i64>], top_n: Option<usize>) -> Result<Vec<Vec<(i32, f32)>>> {
+        let lf_array = self.encode(input_data)?;
+        self.rank_latents(lf_array, top_n)
+    }
+
+    // Keeps the latents next to their ranking, for bulk writers that persist both and for
+    // reconstruction scoring
+    #[cfg(feature = "tensorflow")]
+    pub(crate) fn rank_top_clusters_with_latents(
+        &self,
+        input_data: &[Vec<i64>],
+        top_n: Option<usize>,
+    ) -> Result<(Array2<f32>, Vec<Vec<(i32, f32)>>)> {
+        let lf_array = self.encode(input_data)?;
+        let ranked_clusters = self.rank_latents(lf_array.clone(), top_n)?;
+        Ok((lf_array, ranked_clusters))
+    }
+
+    // One-off ranking under a metric other than the configured one: a throwaway TF graph, or plain
+    // Rust for per-cluster Mahalanobis and builds without TensorFlow
+    fn rank_clusters_with_metric(&self, input_data: &[Vec<i64>], metric: DistanceMetric) -> Result<Vec<Vec<(i32, f32)>>> {
+        #[cfg(feature = "tensorflow")]
+        if !(metric == DistanceMetric::Mahalanobis && self.cluster_covariance.is_some()) {
+            return self.rank_clusters_with(input_data, &metric_distance_fn(metric));
+        }
+
+        self.rank_latents_natively(self.encode(input_data)?, metric, None)
+    }
+
+    #[cfg(feature = "tensorflow")]
+    fn rank_clusters_with<F>(&self, input_data: &[Vec<i64>], distance_fn: &F) -> Result<Vec<Vec<(i32, f32)>>>
+    where
+        F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+    {
+        let lf_array = self.encode(input_data)?;
+        self.rank_latents_with(lf_array, distance_fn)
+    }
+
+    // Ranks with the configured backend and metric, using the cached assignment graphs for TF
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(rows = lf_array.nrows(), dim = lf_array.ncols(), top_n = ?top_n, metric = ?self.config.metric)
+    )]
+    fn rank_latents(&self, lf_array: Array2<f32>, top_n: Option<usize>) -> Result<Vec<Vec<(i32, f32)>>> {
+        let started = Instant::now();
+        let rows = lf_array.nrows();
+
+        let ranked_clusters = self.rank_latents_with_backend(lf_array, top_n);
+
+        if let Err(e) = &ranked_clusters {
+            tracing::error!(error = %e, rows, num_clusters = self.centroids.num_clusters(), "Cluster assignment failed");
+        }
+        instrumentation::record(Stage::Assign, rows, started, &ranked_clusters);
+        ranked_clusters
+    }
+
+    fn rank_latents_with_backend(&self, lf_array: Array2<f32>, top_n: Option<usize>) -> Result<Vec<Vec<(i32, f32)>>> {
+        #[cfg(feature = "tensorflow")]
+        if let Some(assignment_graphs) = &self.assignment_graphs {
+            let (_, sampled_ids) = self.assignment_centroids();
+            return self.rank_assignable_latents(lf_array, sampled_ids, |latents| assignment_graphs.rank(latents, top_n));
+        }
+
+        self.rank_latents_natively(lf_array, self.config.metric, top_n)
+    }
+
+    // Plain Rust ranking; Mahalanobis takes each centroid's covariance when one is loaded
+    fn rank_latents_natively(
+        &self,
+        lf_array: Array2<f32>,
+        metric: DistanceMetric,
+        top_n: Option<usize>,
+    ) -> Result<Vec<Vec<(i32, f32)>>> {
+        let (assignment_centroids, sampled_ids) = self.assignment_centroids();
+
+        self.rank_assignable_latents(lf_array, sampled_ids, |latents| match (metric, &self.cluster_covariance) {
+            (DistanceMetric::Mahalanobis, Some(covariance)) => Ok(covariance.rank(
+                &row_major(latents),
+                &row_major(assignment_centroids),
+                sampled_ids.map(|ids| ids.as_slice()),
+                top_n,
+            )),
+            _ => Ok(rank_native(latents, assignment_centroids, metric, top_n)),
+        })
+    }
+
+    // Builds a one-off assignment graph around a caller-supplied distance graph
+    #[cfg(feature = "tensorflow")]
+    fn rank_latents_with<F>(&self, lf_array: Array2<f32>, distance_fn: &F) -> Result<Vec<Vec<(i32, f32)>>>
+    where
+        F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+    {
+        let (assignment_centroids, sampled_ids) = self.assignment_centroids();
+
+        self.rank_assignable_latents(lf_array, sampled_ids, |latents| {
+            AssignmentGraphs::build(assignment_centroids, distance_fn, self.config.approx_top_k, &self.config.session)?
+                .rank(latents, None)
+        })
+    }
+
+    // Applies the NaN policy, ranks the remaining rows in one batch and maps sampled centroid
+    // indices back to cluster ids
+    fn rank_assignable_latents<R>(
+        &self,
+        mut lf_array: Array2<f32>,
+        sampled_ids: Option<&Vec<i32>>,
+        rank: R,
+    ) -> Result<Vec<Vec<(i32, f32)>>>
+    where
+        R: Fn(&Array2<f32>) -> Result<Vec<Vec<(i32, f32)>>>,
+    {
+        let skipped_rows = self.apply_nan_policy(&mut lf_array)?;
+        let rows = lf_array.nrows();
+
+        let mut ranked_clusters = vec![vec![]; rows];
+        let assigned_rows = (0..rows)
+            .filter(|row_idx| !skipped_rows.contains(row_idx))
+            .collect::<Vec<usize>>();
+
+        if assigned_rows.is_empty() {
+            return Ok(ranked_clusters);
+        }
+
+        // All assignable rows go through the distance graph together in a single session run
+        let assigned_latents = if skipped_rows.is_empty() {
+            lf_array
+        } else {
+            lf_array.select(Axis(0), &assigned_rows)
+        };
+
+        let batch_ranked_clusters = rank(&assigned_latents)?;
+
+        for (row_idx, row) in assigned_rows.into_iter().zip(batch_ranked_clusters) {
+            ranked_clusters[row_idx] = match sampled_ids {
+                Some(cluster_ids) => row
+                    .into_iter()
+                    .map(|(idx, distance)| (cluster_ids[idx as usize], distance))
+                    .collect(),
+                None => row,
+            };
+        }
+
+        Ok(ranked_clusters)
+    }
+
+    // The centroids assignment runs against, with the original cluster id of each row when sampled
+    fn assignment_centroids(&self) -> (&Array2<f32>, Option<&Vec<i32>>) {
+        match &self.sampled_centroids {
+            Some((coordinates, cluster_ids)) => (coordinates, Some(cluster_ids)),
+            None => (&self.centroids.coordinates, None),
+        }
+    }
+
+    /// Runs a small batch of empty fingerprints through the encoder and every cached assignment
+    /// graph, so session initialization and allocations happen before the first real request.
+    /// Usage counts are left untouched.
+    pub fn warm_up(&self) -> Result<()> {
+        let started = Instant::now();
+        let input_dim = self.encoder.input_dim().unwrap_or(BUNDLED_INPUT_DIM);
+        let input_data = vec![vec![0; input_dim]; WARM_UP_BATCH_SIZE];
+
+        let lf_array = self.encode(&input_data)?;
+        if let Some(coarse) = &self.coarse_centroids {
+            coarse.rank(&lf_array, self.config.metric)?;
+        }
+        self.rank_latents(lf_array, None)?;
+
+        tracing::info!("Warmed up encoder model in {:?}", started.elapsed());
+        Ok(())
+    }
+
+    pub fn config(&self) -> &EncoderConfig {
+        &self.config
+    }
 
-impl EncoderModel {
-    pub fn transform(&self, input_data: &[Vec<i64>]) -> eyre::Result<Vec<Vec<i32>>> {
-        let lf_array = self.encode(input_data)?;
-        let cols = lf_array.dims()[1];
-
-        let ranked_cluster_labels = lf_array
-            .chunks(cols as usize)
-            .map(|row_vec| {
-                let row_tensor = Tensor::new(&[1, cols]).with_values(row_vec);
-
-                match row_tensor {
-                    Ok(row_tensor) => {
-                        let cluster_labels = assign_cluster_labels(&row_tensor);
-
-                        cluster_labels.unwrap_or_else(|e| {
-                            log::info!("Failed to retrieve cluster labels: {e}");
-                            vec![]
-                        })
-                    },
-                    Err(e) => {
-                        log::info!("Failed to retrieve tensor row: {e}");
-                        vec![]
-                    },
-                }
-            }).collect::<Vec<Vec<i32>>>();
+    /// The centroids this model assigns against, as loaded or last set by `update_centroids`.
+    pub fn centroids(&self) -> &Centroids {
+        &self.centroids
+    }
 
-        Ok(ranked_cluster_labels)
+    pub fn num_clusters(&self) -> usize {
+        self.centroids.num_clusters()
+    }
+
+    /// Encoder release from the assets manifest, if it names one.
+    pub fn model_version(&self) -> Option<&str> {
+        self.model_version.as_deref()
+    }
+
+    /// Centroid release from the assets manifest, else the date stamp in the centroid file name.
+    pub fn centroid_version(&self) -> Option<&str> {
+        self.centroid_version.as_deref()
+    }
+
+    /// Input fingerprint declared in the assets manifest. When set, every fingerprint passed in
+    /// is checked against it, and wider ones that are a multiple of its width are folded to fit.
+    pub fn fingerprint_spec(&self) -> Option<&FingerprintSpec> {
+        self.fingerprint_spec.as_ref()
+    }
+
+    /// Per-cluster count of top-1 assignments made by `transform` since the model was built.
+    /// Always zero unless `track_usage` is enabled.
+    pub fn cluster_usage_counts(&self) -> Vec<u64> {
+        self.usage_counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    // Counts top-1 labels when usage is tracked; only the public `transform*` entry points call this
+    fn record_usage(&self, ranked_clusters: &[Vec<(i32, f32)>]) {
+        if !self.config.track_usage {
+            return;
+        }
+
+        for (label, _) in ranked_clusters.iter().filter_map(|row| row.first()) {
+            if let Some(count) = self.usage_counts.get(*label as usize) {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Swaps in a new centroid set, rebuilding any sampled subset and HNSW index and resetting
+    /// usage counters. Stored reference latents are kept, see `reassign_reference_library`.
+    pub fn update_centroids(&mut self, centroids: Centroids) -> Result<()> {
+        if centroids.dim() != self.centroids.dim() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "New centroids have dimension {}, expected {}",
+                centroids.dim(),
+                self.centroids.dim()
+            )));
+        }
+
+        if let Some(covariance) = self.cluster_covariance.as_ref().filter(|covariance| covariance.num_clusters() != centroids.num_clusters()) {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "New centroids have {} clusters, but the cluster covariance covers {}",
+                centroids.num_clusters(),
+                covariance.num_clusters()
+            )));
+        }
+
+        self.sampled_centroids = sample_centroids(&centroids, &self.config)?;
+        #[cfg(feature = "tensorflow")]
+        {
+            self.assignment_graphs =
+                build_assignment_graphs(&centroids, &self.sampled_centroids, self.cluster_covariance.as_ref(), &self.config)?;
+        }
+        self.centroid_index = build_centroid_index(&centroids, &self.config);
+        self.usage_counts = new_usage_counts(&centroids);
+        self.centroids = centroids;
+
+        Ok(())
+    }
+
+    /// Encodes a fixed reference library once and keeps its latents on the model, so it can be
+    /// reassigned cheaply after `update_centroids` without re-running the encoder.
+    pub fn set_reference_library(&mut self, inputs: &[Vec<i64>]) -> Result<()> {
+        self.reference_latents = Some(self.encode(inputs)?);
+        Ok(())
+    }
+
+    /// Ranked cluster labels for the stored reference library against the current centroids, cut to
+    /// `top_n`. Does not count towards usage.
+    pub fn reassign_reference_library(&self) -> Result<Vec<Vec<i32>>> {
+        let reference_latents = self
+            .reference_latents
+            .as_ref()
+            .ok_or(EncoderError::NotConfigured("No reference library set".to_string()))?;
+
+        let ranked_clusters = self.rank_latents(reference_latents.clone(), self.config.top_n)?;
+        Ok(ranked_labels(ranked_clusters))
+    }
+
+    pub fn cluster_radii(&self) -> Option<&[f32]> {
+        self.centroids.radii.as_deref()
+    }
+
+    pub fn cluster_radius(&self, cluster_id: i32) -> Option<f32> {
+        let radii = self.centroids.radii.as_ref()?;
+        usize::try_from(cluster_id).ok().and_then(|idx| radii.get(idx).copied())
+    }
+
+    // Returns the indices of rows that should be left unassigned
+    fn apply_nan_policy(&self, lf_array: &mut Array2<f32>) -> Result<Vec<usize>> {
+        let mut skipped_rows = Vec::new();
+
+        for (row_idx, mut row_vec) in lf_array.rows_mut().into_iter().enumerate() {
+            if !row_vec.iter().any(|value| value.is_nan()) {
+                continue;
+            }
+
+            match self.config.nan_policy {
+                NanPolicy::Error => {
+                    tracing::error!(row = row_idx, "Encoder produced NaN latent values");
+                    return Err(EncoderError::NanLatent { row: row_idx });
+                },
+                NanPolicy::SkipRow => {
+                    tracing::warn!(row = row_idx, "Skipping row with NaN latent values; it gets no clusters");
+                    skipped_rows.push(row_idx);
+                },
+                NanPolicy::ZeroFill => row_vec
+                    .iter_mut()
+                    .filter(|value| value.is_nan())
+                    .for_each(|value| *value = 0.0),
+            }
+        }
+
+        Ok(skipped_rows)
+    }
+
+    fn encode(&self, input_data: &[Vec<i64>]) -> Result<Array2<f32>> {
+        let input_data = match &self.fingerprint_spec {
+            Some(fingerprint_spec) => fingerprint_spec.conform(input_data)?,
+            None => Cow::Borrowed(input_data),
+        };
+        self.encode_input(&EncoderInput::Dense(&input_data))
+    }
+
+    // Packed rows are always binary, so they only go through the spec when they need folding
+    fn encode_packed(&self, fingerprints: &PackedFingerprints) -> Result<Array2<f32>> {
+        match &self.fingerprint_spec {
+            Some(fingerprint_spec) if fingerprint_spec.num_bits != fingerprints.num_bits() => {
+                self.encode(&fingerprints.unpack())
+            },
+            _ => self.encode_input(&EncoderInput::Packed(*fingerprints)),
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(rows = input.num_rows(), width = input.row_length()))]
+    fn encode_input(&self, input: &EncoderInput) -> Result<Array2<f32>> {
+        let started = Instant::now();
+        let lf_array = self
+            .encoder
+            .encode(input)
+            .and_then(|lf_array| self.check_latent_width(&lf_array).map(|_| lf_array));
+
+        if let Err(e) = &lf_array {
+            tracing::error!(
+                error = %e,
+                rows = input.num_rows(),
+                width = input.row_length(),
+                expected_width = ?self.encoder.input_dim(),
+                "Encoding failed"
+            );
+        }
+        instrumentation::record(Stage::Encode, input.num_rows(), started, &lf_array);
+        lf_array
+    }
+
+    #[cfg(feature = "tensorflow")]
+    #[tracing::instrument(level = "debug", skip_all, fields(inputs = named_inputs.len()))]
+    fn encode_named(&self, named_inputs: &HashMap<String, Array2<f32>>) -> Result<Array2<f32>> {
+        let started = Instant::now();
+        let rows = named_inputs.values().next().map_or(0, Array2::nrows);
+        let lf_array = self
+            .encoder
+            .encode_named(named_inputs)
+            .and_then(|lf_array| self.check_latent_width(&lf_array).map(|_| lf_array));
+
+        if let Err(e) = &lf_array {
+            let shapes = named_inputs
+                .iter()
+                .map(|(name, input)| (name.as_str(), input.dim()))
+                .collect::<Vec<(&str, (usize, usize))>>();
+            tracing::error!(error = %e, ?shapes, "Encoding named inputs failed");
+        }
+        instrumentation::record(Stage::Encode, rows, started, &lf_array);
+        lf_array
+    }
+
+    // Catches models without a static latent width whose output doesn't fit the centroids
+    fn check_latent_width(&self, lf_array: &Array2<f32>) -> Result<()> {
+        let latent_dim = lf_array.ncols();
+        if latent_dim != self.centroids.dim() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Encoder produced {}-dimensional latents, but the centroids are {}-dimensional",
+                latent_dim,
+                self.centroids.dim()
+            )));
+        }
+
+        Ok(())
     }
 
-    fn encode(&self, input_data: &[Vec<i64>]) -> eyre::Result<Tensor<f32>> {
-        let rows = input_data.len() as u64;
-        let cols = input_data[0].len() as u64;
+}
+
+pub fn build_encoder_model() -> Result<EncoderModel> {
+    build_encoder_model_with_config(EncoderConfig::default())
+}
+
+pub fn build_encoder_model_with_config(config: EncoderConfig) -> Result<EncoderModel> {
+    // Before the assets search, so a bad config isn't masked by missing assets
+    config.validate()?;
+    load_model_from_assets(&assets_path()?, config)
+}
 
-        let flattened_input = input_data.concat();
-        let input_tensor = Tensor::new(&[rows, cols]).with_values(&flattened_input)?;
+/// Startup-time loading: validates the config, resolves the assets path, reports every missing
+/// asset at once, parses the centroids, loads the encoder and runs a warm-up batch through every
+/// graph, so a broken deployment fails here instead of inside the first `transform`.
+pub fn init_encoder_model(config: EncoderConfig) -> Result<EncoderModel> {
+    let started = Instant::now();
+    let encoder_model = build_encoder_model_with_config(config)?;
+    encoder_model.warm_up()?;
 
-        let input_operation = self
-            .graph
-            .operation_by_name("serving_default_dense_input")?
-            .ok_or(eyre::eyre!("No operation found"))?;
+    tracing::info!(
+        "Initialized encoder model with {} clusters in {:?}",
+        encoder_model.num_clusters(),
+        started.elapsed()
+    );
+    Ok(encoder_model)
+}
 
-        let output_operation = self
-            .graph
-            .operation_by_name("StatefulPartitionedCall")?
-            .ok_or(eyre::eyre!("No operation found"))?;
+/// Loads the encoder and centroids from an explicit assets directory, e.g. one shipped next to a
+/// deployed binary.
+pub fn build_encoder_model_from_path(path: &str) -> Result<EncoderModel> {
+    load_model_from_assets(path, EncoderConfig::default())
+}
 
-        let mut run_args = SessionRunArgs::new();
-        run_args.add_feed(&input_operation, 0, &input_tensor);
+pub fn build_encoder_model_from_archive(path: &str) -> Result<EncoderModel> {
+    let extract_dir = tempfile::tempdir()?;
+    extract_archive(Path::new(path), extract_dir.path())?;
 
-        let output_token = run_args.request_fetch(&output_operation, 0);
-        self.encoder.session.run(&mut run_args)?;
+    let config = EncoderConfig::default();
+    let assets_path = find_assets_root(extract_dir.path(), &config.encoder_dir)?;
+    load_model_from_assets(&assets_path.to_string_lossy(), config)
+}
 
-        let output_tensor = run_args.fetch(output_token)?;
-        Ok(output_tensor)
+/// Reads `config.centroids_file` under `assets_path` once so the later parse is served from the OS
+/// page cache. A no-op for embedded centroids.
+pub fn prefetch_centroids(assets_path: &str, config: &EncoderConfig) -> Result<()> {
+    if config.embedded_centroids {
+        return Ok(());
     }
+
+    let centroids_path = format!("{}/{}", assets_path, config.centroids_file);
+    let mut centroids_file = File::open(centroids_path)?;
+    std::io::copy(&mut centroids_file, &mut std::io::sink())?;
+
+    Ok(())
 }
 
-pub fn build_encoder_model() -> eyre::Result<EncoderModel> {
-    let (encoder, graph) = load_encoder_model()?;
+pub(crate) fn load_model_from_assets(assets_path: &str, mut config: EncoderConfig) -> Result<EncoderModel> {
+    config.validate()?;
+    check_assets(assets_path, &config)?;
+    let encoder = Encoder::load(assets_path, &config)?;
+    let centroids_path = format!("{}/{}", assets_path, config.centroids_file);
+    let centroids = load_model_centroids(&centroids_path, &encoder, &config)?;
+    apply_metadata_temperature(&mut config, &centroids_path)?;
+    report_duplicate_centroids(&centroids, &config);
+    let sampled_centroids = sample_centroids(&centroids, &config)?;
+    let cluster_covariance = load_model_covariance(assets_path, &centroids, &config)?;
+    #[cfg(feature = "tensorflow")]
+    let assignment_graphs = build_assignment_graphs(&centroids, &sampled_centroids, cluster_covariance.as_ref(), &config)?;
+    let centroid_index = build_centroid_index(&centroids, &config);
+    let coarse_centroids = load_coarse_centroids(assets_path, &centroids, &config)?;
+    let usage_counts = new_usage_counts(&centroids);
+    let manifest = load_asset_manifest(assets_path)?.unwrap_or_default();
+    let centroid_version = manifest.centroid_version.or_else(|| date_stamp(&config.centroids_file));
 
     Ok(
         EncoderModel {
             encoder,
-            graph,
+            centroids,
+            sampled_centroids,
+            #[cfg(feature = "tensorflow")]
+            assignment_graphs,
+            centroid_index,
+            coarse_centroids,
+            cluster_covariance,
+            config,
+            usage_counts,
+            reference_latents: None,
+            model_version: manifest.model_version,
+            centroid_version,
+            fingerprint_spec: manifest.fingerprint,
         }
     )
 }
 
-fn assign_cluster_labels(lf_array: &Tensor<f32>) -> eyre::Result<Vec<i32>> {
+// Reports every missing asset at once rather than failing on whichever one loads first
+fn check_assets(assets_path: &str, config: &EncoderConfig) -> Result<()> {
+    let model_path = model_asset_path(assets_path, config);
+    let centroids_path = Path::new(assets_path).join(&config.centroids_file);
+    let centroids_required = !config.embedded_centroids;
+
+    let mut missing = Vec::new();
+    if !model_path.exists() {
+        missing.push(format!("encoder model {}", model_path.display()));
+    }
+    if !centroids_path.is_file() && centroids_required {
+        missing.push(format!("centroid file {}", centroids_path.display()));
+    }
+    if let Some(cluster_covariance_file) = &config.cluster_covariance_file {
+        let cluster_covariance_path = Path::new(assets_path).join(cluster_covariance_file);
+        if !cluster_covariance_path.is_file() {
+            missing.push(format!("cluster covariance file {}", cluster_covariance_path.display()));
+        }
+    }
+    if let Some(coarse_centroids_file) = &config.coarse_centroids_file {
+        let coarse_centroids_path = Path::new(assets_path).join(coarse_centroids_file);
+        if !coarse_centroids_path.is_file() {
+            missing.push(format!("coarse centroid file {}", coarse_centroids_path.display()));
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(EncoderError::MissingAssets(format!(
+            "Missing assets under {}: {}",
+            assets_path,
+            missing.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+// A mismatch with the model's declared latent width fails here rather than on first transform
+fn load_model_centroids(centroids_path: &str, encoder: &Encoder, config: &EncoderConfig) -> Result<Centroids> {
+    #[cfg(feature = "embedded-centroids")]
+    if config.embedded_centroids {
+        let centroids = load_centroids_from_embedded(&config.centroid_layout)?;
+        if let Some(latent_dim) = encoder.latent_dim().filter(|&dim| dim != centroids.dim()) {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Embedded centroids are {}-dimensional, but the encoder produces {}-dimensional latents",
+                centroids.dim(),
+                latent_dim
+            )));
+        }
+
+        return Ok(centroids);
+    }
+
+    load_centroids_file(centroids_path, &config.centroid_layout, encoder.latent_dim())
+}
+
+fn apply_metadata_temperature(config: &mut EncoderConfig, centroids_path: &str) -> Result<()> {
+    if !config.metadata_temperature {
+        return Ok(());
+    }
+
+    let temperature = load_centroid_metadata(centroids_path)?.and_then(|metadata| metadata.temperature);
+
+    if let (Some(calibrated), WeightKernel::Exponential { temperature }) = (temperature, &mut config.kernel) {
+        if calibrated <= 0.0 {
+            return Err(EncoderError::CentroidParse(format!("Centroid metadata temperature must be positive, got {}", calibrated)));
+        }
+
+        tracing::info!("Using calibrated temperature {} from centroid metadata", calibrated);
+        *temperature = calibrated;
+    }
+
+    Ok(())
+}
+
+fn report_duplicate_centroids(centroids: &Centroids, config: &EncoderConfig) {
+    let Some(epsilon) = config.duplicate_centroid_epsilon else {
+        return;
+    };
+
+    let duplicates = centroids.find_duplicates(epsilon);
+    if !duplicates.is_empty() {
+        tracing::info!(
+            "Found {} centroid pairs within {} of each other: {:?}",
+            duplicates.len(),
+            epsilon,
+            duplicates
+        );
+    }
+}
+
+/// Collapses cluster ids, in any order and with repeats, into sorted inclusive ranges of
+/// consecutive ids.
+pub fn cluster_ranges(cluster_ids: impl IntoIterator<Item = i32>) -> Vec<(i32, i32)> {
+    let mut cluster_ids = cluster_ids.into_iter().collect::<Vec<i32>>();
+    cluster_ids.sort_unstable();
+    cluster_ids.dedup();
+
+    let mut ranges: Vec<(i32, i32)> = Vec::new();
+    for id in cluster_ids {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == id => *end = id,
+            _ => ranges.push((id, id)),
+        }
+    }
+
+    ranges
+}
+
+fn sample_centroids(centroids: &Centroids, config: &EncoderConfig) -> Result<Option<(Array2<f32>, Vec<i32>)>> {
+    match config.centroid_sampling {
+        CentroidSampling::Full => Ok(None),
+        ref sampling => Ok(Some(centroids.subsample(sampling)?)),
+    }
+}
+
+#[cfg(feature = "tensorflow")]
+fn build_assignment_graphs(
+    centroids: &Centroids,
+    sampled_centroids: &Option<(Array2<f32>, Vec<i32>)>,
+    cluster_covariance: Option<&ClusterCovariance>,
+    config: &EncoderConfig,
+) -> Result<Option<AssignmentGraphs>> {
+    if config.assignment_backend == AssignmentBackend::Native {
+        return Ok(None);
+    }
+
+    let (assignment_centroids, sampled_ids) = match sampled_centroids {
+        Some((coordinates, cluster_ids)) => (coordinates, Some(cluster_ids.as_slice())),
+        None => (&centroids.coordinates, None),
+    };
+
+    let assignment_graphs = match cluster_covariance.filter(|_| config.metric == DistanceMetric::Mahalanobis) {
+        Some(covariance) => {
+            let Some(inverse_variances) = covariance.diagonal_rows(sampled_ids) else {
+                tracing::info!("Full-covariance Mahalanobis has no TF graph, assigning with the native backend");
+                return Ok(None);
+            };
+
+            let inverse_variances = Array2::from_shape_vec(assignment_centroids.dim(), inverse_variances)?;
+            AssignmentGraphs::build(
+                assignment_centroids,
+                &|scope: &mut Scope, centroids_input: Output, lf_input: Output| {
+                    diagonal_mahalanobis_distance(scope, centroids_input, lf_input, &inverse_variances)
+                },
+                config.approx_top_k,
+                &config.session,
+            )?
+        }
+        None => AssignmentGraphs::build(
+            assignment_centroids,
+            &metric_distance_fn(config.metric),
+            config.approx_top_k,
+            &config.session,
+        )?,
+    };
+    Ok(Some(assignment_graphs))
+}
+
+fn load_model_covariance(assets_path: &str, centroids: &Centroids, config: &EncoderConfig) -> Result<Option<ClusterCovariance>> {
+    let Some(cluster_covariance_file) = &config.cluster_covariance_file else {
+        if config.metric == DistanceMetric::Mahalanobis {
+            return Err(EncoderError::NotConfigured(
+                "Mahalanobis distance needs a cluster_covariance_file".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    let cluster_covariance_path = format!("{}/{}", assets_path, cluster_covariance_file);
+    let covariance = load_cluster_covariance(&cluster_covariance_path, centroids.num_clusters(), centroids.dim())?;
+    Ok(Some(covariance))
+}
+
+fn load_coarse_centroids(assets_path: &str, centroids: &Centroids, config: &EncoderConfig) -> Result<Option<CoarseCentroids>> {
+    let Some(coarse_centroids_file) = &config.coarse_centroids_file else {
+        return Ok(None);
+    };
+
+    let coarse_centroids_path = format!("{}/{}", assets_path, coarse_centroids_file);
+    let coarse = load_centroids_file(&coarse_centroids_path, &config.centroid_layout, Some(centroids.dim()))?;
+    #[cfg(feature = "tensorflow")]
+    let assignment_graphs = build_assignment_graphs(&coarse, &None, None, config)?;
+
+    Ok(Some(CoarseCentroids {
+        centroids: coarse,
+        #[cfg(feature = "tensorflow")]
+        assignment_graphs,
+    }))
+}
+
+fn build_centroid_index(centroids: &Centroids, config: &EncoderConfig) -> Option<CentroidIndex> {
+    config
+        .hnsw
+        .as_ref()
+        .map(|hnsw_config| CentroidIndex::build(centroids, hnsw_config, config.metric))
+}
+
+// Drops the distances, keeping the ranked labels
+fn ranked_labels(ranked_clusters: Vec<Vec<(i32, f32)>>) -> Vec<Vec<i32>> {
+    ranked_clusters
+        .into_iter()
+        .map(|row| row.into_iter().map(|(label, _)| label).collect())
+        .collect()
+}
+
+fn new_usage_counts(centroids: &Centroids) -> Vec<AtomicU64> {
+    (0..centroids.num_clusters()).map(|_| AtomicU64::new(0)).collect()
+}
+
+#[cfg(feature = "tensorflow")]
+fn assignment_graph_def<F>(centroids: &Array2<f32>, distance_fn: &F) -> Result<Vec<u8>>
+where
+    F: Fn(&mut Scope, Output, Output) -> Result<Output>,
+{
     let mut scope = Scope::new_root_scope();
-    let mut run_args = SessionRunArgs::new();
 
-    let centroids_input = ops::Placeholder::new()
+    let centroids_const = ops::Const::new()
         .dtype(DataType::Float)
-        .shape(CENTROIDS.dims())
-        .build(&mut scope)?;
+        .value(to_tensor(centroids)?)
+        .build(&mut scope.with_op_name("centroids"))?;
 
+    let latent_shape = [-1, centroids.ncols() as i64];
     let lf_input = ops::Placeholder::new()
         .dtype(DataType::Float)
-        .shape(lf_array.dims())
+        .shape(&latent_shape[..])
+        .build(&mut scope.with_op_name("latent"))?;
+
+    let distance = distance_fn(&mut scope, centroids_const.into(), lf_input.into())?;
+
+    let negated_distance = ops::Neg::new()
+        .build(distance, &mut scope)?;
+
+    let k_tensor = ops::Const::new()
+        .dtype(DataType::Int64)
+        .value(centroids.nrows() as i64)
         .build(&mut scope)?;
 
-    run_args.add_feed(&centroids_input, 0, &CENTROIDS);
-    run_args.add_feed(&lf_input, 0, lf_array);
+    let top_k = ops::TopKV2::new()
+        .build(negated_distance, k_tensor, &mut scope)?;
+
+    ops::Neg::new()
+        .build(Output { operation: top_k.clone(), index: 0 }, &mut scope.with_op_name("distances"))?;
+
+    ops::Identity::new()
+        .build(Output { operation: top_k, index: 1 }, &mut scope.with_op_name("cluster_labels"))?;
+
+    let graph_def = scope.graph().graph_def()?;
+    Ok(graph_def)
+}
+
+#[cfg(feature = "tensorflow")]
+type DistanceFn = fn(&mut Scope, Output, Output) -> Result<Output>;
+
+// Graph builder computing `metric` between the centroid and latent placeholders
+#[cfg(feature = "tensorflow")]
+fn metric_distance_fn(metric: DistanceMetric) -> DistanceFn {
+    match metric {
+        // Without a cluster to take covariance from, Mahalanobis is the identity-covariance RMS
+        DistanceMetric::Euclidean | DistanceMetric::Mahalanobis => euclidean_distance,
+        DistanceMetric::Cosine => cosine_distance,
+        DistanceMetric::Manhattan => manhattan_distance,
+    }
+}
 
-    let begin_tensor = ops::Const::new()
+// Diagonal Mahalanobis distance from every latent row to every centroid as a `[batch, num_clusters]`
+// output, with `inverse_variances` aligned to the centroid rows. Expanded like `euclidean_distance`
+// as `x^2 . w - 2 x . (c * w) + c^2 . w` per cluster weight row `w`
+#[cfg(feature = "tensorflow")]
+fn diagonal_mahalanobis_distance(
+    scope: &mut Scope,
+    centroids_input: Output,
+    lf_input: Output,
+    inverse_variances: &Array2<f32>,
+) -> Result<Output> {
+    let dim_axis = ops::Const::new()
         .dtype(DataType::Int32)
-        .value(Tensor::new(&[2]).with_values(&[0, 0])?)
-        .build(&mut scope)?;
+        .value(Tensor::new(&[1]).with_values(&[1])?)
+        .build(scope)?;
+
+    let weights = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(to_tensor(inverse_variances)?)
+        .build(scope)?;
+
+    let squared_lf = ops::Square::new()
+        .build(lf_input.clone(), scope)?;
+
+    // [batch, num_clusters]
+    let weighted_lf_norms = ops::MatMul::new()
+        .transpose_b(true)
+        .build(squared_lf, weights.clone(), scope)?;
+
+    let weighted_centroids = ops::Mul::new()
+        .build(centroids_input.clone(), weights.clone(), scope)?;
+
+    let cross_products = ops::MatMul::new()
+        .transpose_b(true)
+        .build(lf_input, weighted_centroids, scope)?;
+
+    let squared_centroids = ops::Square::new()
+        .build(centroids_input, scope)?;
+
+    let weighted_squared_centroids = ops::Mul::new()
+        .build(squared_centroids, weights, scope)?;
+
+    // [num_clusters], broadcast across the batch
+    let weighted_centroid_norms = ops::Sum::new()
+        .build(weighted_squared_centroids, dim_axis, scope)?;
+
+    let two = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(2.0f32)
+        .build(scope)?;
+
+    let scaled_cross_products = ops::Mul::new()
+        .build(two, cross_products, scope)?;
+
+    let norms = ops::Add::new()
+        .build(weighted_lf_norms, weighted_centroid_norms, scope)?;
+
+    let squared_distance = ops::Sub::new()
+        .build(norms, scaled_cross_products, scope)?;
+
+    let zero = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(0.0f32)
+        .build(scope)?;
+
+    let clamped_squared_distance = ops::Maximum::new()
+        .build(squared_distance, zero, scope)?;
+
+    let dim = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(inverse_variances.ncols() as f32)
+        .build(scope)?;
+
+    let mean_squared_diff = ops::Div::new()
+        .build(clamped_squared_distance, dim, scope)?;
+
+    let distance = ops::Sqrt::new()
+        .build(mean_squared_diff, scope)?;
+
+    Ok(distance.into())
+}
+
+// `1 - cos(x, c)` from every latent row to every centroid as a `[batch, num_clusters]` output
+#[cfg(feature = "tensorflow")]
+fn cosine_distance(scope: &mut Scope, centroids_input: Output, lf_input: Output) -> Result<Output> {
+    let normalized_lf = l2_normalize(scope, lf_input)?;
+    let normalized_centroids = l2_normalize(scope, centroids_input)?;
+
+    let similarities = ops::MatMul::new()
+        .transpose_b(true)
+        .build(normalized_lf, normalized_centroids, scope)?;
+
+    let one = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(1.0f32)
+        .build(scope)?;
+
+    let distance = ops::Sub::new()
+        .build(one, similarities, scope)?;
+
+    // Rounding can push near-identical directions just below zero
+    let zero = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(0.0f32)
+        .build(scope)?;
+
+    let clamped_distance = ops::Maximum::new()
+        .build(distance, zero, scope)?;
+
+    Ok(clamped_distance.into())
+}
 
-    let size_tensor = ops::Const::new()
+// Scales rows to unit length; zero rows stay zero, so their cosine distance to anything is 1
+#[cfg(feature = "tensorflow")]
+fn l2_normalize(scope: &mut Scope, input: Output) -> Result<Output> {
+    let dim_axis = ops::Const::new()
         .dtype(DataType::Int32)
-        .value(Tensor::new(&[2]).with_values(&[1, 128])?)
-        .build(&mut scope)?;
+        .value(Tensor::new(&[1]).with_values(&[1])?)
+        .build(scope)?;
+
+    let squared = ops::Square::new()
+        .build(input.clone(), scope)?;
+
+    let squared_norms = ops::Sum::new()
+        .keep_dims(true)
+        .build(squared, dim_axis, scope)?;
+
+    let epsilon = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(1e-12f32)
+        .build(scope)?;
+
+    let clamped_squared_norms = ops::Maximum::new()
+        .build(squared_norms, epsilon, scope)?;
+
+    let inverse_norms = ops::Rsqrt::new()
+        .build(clamped_squared_norms, scope)?;
+
+    let normalized = ops::Mul::new()
+        .build(input, inverse_norms, scope)?;
+
+    Ok(normalized.into())
+}
+
+// Mean absolute difference from every latent row to every centroid as a `[batch, num_clusters]`
+// output. Unlike the other metrics this broadcasts a `[batch, num_clusters, dim]` difference
+// tensor, so large batches are better served by `AssignmentBackend::Native`
+#[cfg(feature = "tensorflow")]
+fn manhattan_distance(scope: &mut Scope, centroids_input: Output, lf_input: Output) -> Result<Output> {
+    let batch_axis = ops::Const::new()
+        .dtype(DataType::Int32)
+        .value(1i32)
+        .build(scope)?;
+
+    let cluster_axis = ops::Const::new()
+        .dtype(DataType::Int32)
+        .value(0i32)
+        .build(scope)?;
+
+    // [batch, 1, dim]
+    let expanded_lf = ops::ExpandDims::new()
+        .build(lf_input, batch_axis, scope)?;
 
-    let lf_slice = ops::Slice::new()
-        .build(lf_input, begin_tensor, size_tensor, &mut scope)?;
+    // [1, num_clusters, dim]
+    let expanded_centroids = ops::ExpandDims::new()
+        .build(centroids_input, cluster_axis, scope)?;
 
-    let diff = ops::Sub::new()
-        .build(centroids_input, lf_slice, &mut scope)?;
+    let differences = ops::Sub::new()
+        .build(expanded_lf, expanded_centroids, scope)?;
 
-    let squared_diff = ops::Square::new()
-        .build(diff, &mut scope)?;
+    let absolute_differences = ops::Abs::new()
+        .build(differences, scope)?;
 
-    let axis_tensor = ops::Const::new()
+    let dim_axis = ops::Const::new()
+        .dtype(DataType::Int32)
+        .value(2i32)
+        .build(scope)?;
+
+    let distance = ops::Mean::new()
+        .build(absolute_differences, dim_axis, scope)?;
+
+    Ok(distance.into())
+}
+
+// RMS distance from every latent row to every centroid as a `[batch, num_clusters]` output.
+// Expanded as `|x|^2 - 2 x.c + |c|^2` so a batch never materializes a
+// `[batch, num_clusters, dim]` difference tensor
+#[cfg(feature = "tensorflow")]
+fn euclidean_distance(scope: &mut Scope, centroids_input: Output, lf_input: Output) -> Result<Output> {
+    let dim_axis = ops::Const::new()
         .dtype(DataType::Int32)
         .value(Tensor::new(&[1]).with_values(&[1])?)
-        .build(&mut scope)?;
+        .build(scope)?;
 
-    let mean_squared_diff = ops::Mean::new()
-        .build(squared_diff, axis_tensor, &mut scope)?;
+    let squared_lf = ops::Square::new()
+        .build(lf_input.clone(), scope)?;
 
-    let distance = ops::Sqrt::new()
-        .build(mean_squared_diff, &mut scope)?;
+    // [batch, 1]
+    let lf_norms = ops::Sum::new()
+        .keep_dims(true)
+        .build(squared_lf, dim_axis.clone(), scope)?;
 
-    let negated_distance = ops::Neg::new()
-        .build(distance, &mut scope)?;
+    let squared_centroids = ops::Square::new()
+        .build(centroids_input.clone(), scope)?;
 
-    let k_tensor = ops::Const::new()
-        .dtype(DataType::Int64)
-        .value(CENTROIDS.dims()[0] as i64)
-        .build(&mut scope)?;
+    // [num_clusters], broadcast across the batch
+    let centroid_norms = ops::Sum::new()
+        .build(squared_centroids, dim_axis, scope)?;
 
-    let top_k = ops::TopKV2::new()
-        .build(negated_distance, k_tensor, &mut scope)?;
+    let cross_products = ops::MatMul::new()
+        .transpose_b(true)
+        .build(lf_input, centroids_input.clone(), scope)?;
 
-    let graph = scope.graph();
-    let session = Session::new(&SessionOptions::new(), &graph)?;
+    let two = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(2.0f32)
+        .build(scope)?;
 
-    let top_k_token = run_args.request_fetch(&top_k, 1);
-    session.run(&mut run_args)?;
+    let scaled_cross_products = ops::Mul::new()
+        .build(two, cross_products, scope)?;
+
+    let norms = ops::Add::new()
+        .build(lf_norms, centroid_norms, scope)?;
+
+    let squared_distance = ops::Sub::new()
+        .build(norms, scaled_cross_products, scope)?;
+
+    // Cancellation can leave tiny negative values for near-identical vectors
+    let zero = ops::Const::new()
+        .dtype(DataType::Float)
+        .value(0.0f32)
+        .build(scope)?;
+
+    let clamped_squared_distance = ops::Maximum::new()
+        .build(squared_distance, zero, scope)?;
+
+    let shape = ops::Shape::new()
+        .out_type(DataType::Int32)
+        .build(centroids_input, scope)?;
+
+    let dim_index = ops::Const::new()
+        .dtype(DataType::Int32)
+        .value(1i32)
+        .build(scope)?;
+
+    let gather_axis = ops::Const::new()
+        .dtype(DataType::Int32)
+        .value(0i32)
+        .build(scope)?;
+
+    let dim = ops::GatherV2::new()
+        .build(shape, dim_index, gather_axis, scope)?;
 
-    let ranked_cluster_labels = run_args.fetch(top_k_token)?;
-    let ranked_cluster_labels = ranked_cluster_labels.iter().as_slice().to_vec();
+    let dim = ops::Cast::new()
+        .DstT(DataType::Float)
+        .build(dim, scope)?;
 
-    Ok(ranked_cluster_labels)
+    let mean_squared_diff = ops::Div::new()
+        .build(clamped_squared_distance, dim, scope)?;
+
+    let distance = ops::Sqrt::new()
+        .build(mean_squared_diff, scope)?;
+
+    Ok(distance.into())
 }
 
-fn load_cluster_centroids() -> eyre::Result<Tensor<f32>> {
-    let centroids_path = format!("{}/lf_kmeans_10k_centroids_20241111.csv", ASSETS_PATH.as_str());
+#[cfg(feature = "tensorflow")]
+fn pairwise_distances(
+    lf_a: &Array2<f32>,
+    lf_b: &Array2<f32>,
+    distance_fn: DistanceFn,
+    session_config: &SessionConfig,
+) -> Result<Tensor<f32>> {
+    let mut scope = Scope::new_root_scope();
+    let mut run_args = SessionRunArgs::new();
+    let lf_a = to_tensor(lf_a)?;
+    let lf_b = to_tensor(lf_b)?;
+
+    let lf_a_input = ops::Placeholder::new()
+        .dtype(DataType::Float)
+        .shape(lf_a.dims())
+        .build(&mut scope)?;
+
+    let lf_b_input = ops::Placeholder::new()
+        .dtype(DataType::Float)
+        .shape(lf_b.dims())
+        .build(&mut scope)?;
 
-    let centroid_vec = read_to_string(centroids_path)?
-        .lines()
-        .map(|line| {
-            line.split(',')
-                .map(|value| f32::from_str(value.trim()).unwrap())
-                .collect()
-        })
-        .collect::<Vec<Vec<f32>>>();
+    run_args.add_feed(&lf_a_input, 0, &lf_a);
+    run_args.add_feed(&lf_b_input, 0, &lf_b);
 
-    let array: Array2<f32> = Array2::from_shape_vec((centroid_vec.len(), centroid_vec[0].len()), centroid_vec.concat())?;
-    let array_slice = array.as_slice().ok_or(eyre::eyre!("Failed to convert array to slice"))?;
+    // B plays the centroids, so rows follow A and columns follow B
+    let distance = distance_fn(&mut scope, lf_b_input.into(), lf_a_input.into())?;
 
-    let tensor = Tensor::new(&[array.shape()[0] as u64, array.shape()[1] as u64])
-        .with_values(array_slice)?;
+    let graph = scope.graph();
+    let session = Session::new(&assignment_session_options(session_config)?, &graph)?;
+
+    let distance_token = run_args.request_fetch(&distance.operation, distance.index);
+    session.run(&mut run_args)?;
 
-    Ok(tensor)
+    let distances = run_args.fetch(distance_token)?;
+    Ok(distances)
 }
 
-fn load_encoder_model() -> eyre::Result<(SavedModelBundle, Graph)> {
-    let session_options = SessionOptions::new();
-    let mut graph = Graph::new();
-    let model_dir = format!("{}/vae_encoder", ASSETS_PATH.as_str());
-    let saved_model = SavedModelBundle::load(&session_options, vec!["serve"], &mut graph, model_dir)?;
+// Resolved on every call rather than cached, so a failed search is reported by whichever load
+// hits it and later loads can still succeed once the assets are in place
+pub(crate) fn assets_path() -> Result<String> {
+    get_assets_path().map_err(|e| EncoderError::MissingAssets(format!("Failed to resolve assets path: {}", e)))
+}
 
-    Ok((saved_model, graph))
+pub fn find_assets_path() -> Option<String> {
+    get_assets_path().ok()
 }
 
-pub fn get_assets_path() -> eyre::Result<String> {
-    let crate_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+pub fn get_assets_path() -> Result<String> {
+    if let Ok(assets_path) = std::env::var(ASSETS_ENV_VAR) {
+        if !Path::new(&assets_path).is_dir() {
+            return Err(EncoderError::MissingAssets(format!("{} points to {}, which is not a directory", ASSETS_ENV_VAR, assets_path)));
+        }
+
+        return Ok(assets_path);
+    }
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|e| EncoderError::MissingAssets(format!("CARGO_MANIFEST_DIR: {}", e)))?;
     let target_dir = format!("{}/target", crate_dir);
     let build_type = if cfg!(debug_assertions) {
         "debug"
@@ -212,12 +1993,12 @@ pub fn get_assets_path() -> eyre::Result<String> {
                     }
                 }
             },
-            Err(e) => return Err(eyre::eyre!("Caught an exception while searching for the assets path: {}", e))
+            Err(e) => return Err(EncoderError::MissingAssets(format!("Caught an exception while searching for the assets path: {}", e)))
         }
     }
 
     if assets_path.is_empty() {
-        return Err(eyre::eyre!("Failed to find assets path"))
+        return Err(EncoderError::MissingAssets("Failed to find assets path".to_string()))
     }
 
     Ok(assets_path)
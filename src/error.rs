@@ -0,0 +1,59 @@
+use thiserror::Error;
+
+/// Everything the library can fail with, split by cause so callers can tell bad input from a
+/// broken deployment.
+#[derive(Debug, Error)]
+pub enum EncoderError {
+    /// The encoder model, centroid file or assets directory could not be found
+    #[error("{0}")]
+    MissingAssets(String),
+    /// Fingerprints, latents or centroids don't have the shape an operation expects
+    #[error("{0}")]
+    ShapeMismatch(String),
+    /// An argument or config value is outside its valid range
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// A centroid CSV or its metadata sidecar could not be parsed
+    #[error("{0}")]
+    CentroidParse(String),
+    /// The asset manifest could not be parsed
+    #[error("{0}")]
+    Manifest(String),
+    /// An optional component the call relies on was never set up
+    #[error("{0}")]
+    NotConfigured(String),
+    /// The encoder produced `NaN` latents under `NanPolicy::Error`
+    #[error("Encoder produced NaN latent values for row {row}")]
+    NanLatent { row: usize },
+    #[error("Transform cancelled after {assigned} of {total} rows")]
+    Cancelled { assigned: usize, total: usize },
+    /// Reading or writing an Arrow, Parquet or latent index file failed
+    #[error("{0}")]
+    Format(String),
+    /// Fetching remote assets failed
+    #[error("{0}")]
+    Download(String),
+    #[error("Checksum mismatch: expected SHA-256 {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+    /// Building or running a TensorFlow graph or session failed
+    #[cfg(feature = "tensorflow")]
+    #[error("TensorFlow error: {0}")]
+    TensorFlow(#[from] tensorflow::Status),
+    /// The encoder model doesn't have the inputs and outputs the crate expects, or a
+    /// non-TensorFlow backend failed to load or run it
+    #[error("{0}")]
+    Model(String),
+    #[cfg(feature = "model")]
+    #[error("Failed to read archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, EncoderError>;
+
+impl From<ndarray::ShapeError> for EncoderError {
+    fn from(e: ndarray::ShapeError) -> Self {
+        EncoderError::ShapeMismatch(e.to_string())
+    }
+}
@@ -0,0 +1,143 @@
+//! C ABI for non-Rust callers; the matching header is `include/cheminee_similarity.h`.
+//!
+//! Functions never unwind across the boundary. On failure they return null or a nonzero status
+//! and the message is available from `csw_last_error` on the same thread.
+use crate::encoder::{build_encoder_model, build_encoder_model_from_path, EncoderModel};
+use crate::error::{EncoderError, Result};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Label written for ranks a row has no cluster for, e.g. rows skipped by the NaN policy
+pub const CSW_NO_LABEL: i32 = -1;
+
+/// Opaque handle to a loaded model
+pub struct CswModel {
+    model: EncoderModel,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+fn catch_result<T>(f: impl FnOnce() -> Result<T>) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            None
+        }
+        Err(_) => {
+            set_last_error("Panic inside cheminee-similarity-model".to_string());
+            None
+        }
+    }
+}
+
+/// Loads a model from an assets directory, or the default assets when `assets_path` is null.
+/// Returns null on failure. Free the model with `csw_free`.
+///
+/// # Safety
+/// `assets_path` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn csw_model_load(assets_path: *const c_char) -> *mut CswModel {
+    let model = catch_result(|| {
+        let model = if assets_path.is_null() {
+            build_encoder_model()?
+        } else {
+            let path = CStr::from_ptr(assets_path)
+                .to_str()
+                .map_err(|e| EncoderError::InvalidArgument(format!("assets_path is not UTF-8: {}", e)))?;
+            build_encoder_model_from_path(path)?
+        };
+
+        Ok(Box::new(CswModel { model }))
+    });
+
+    model.map_or(ptr::null_mut(), Box::into_raw)
+}
+
+/// Assigns the nearest `top_n` clusters to `num_rows` row-major fingerprints of `num_bits` each,
+/// writing `num_rows * top_n` labels to `labels_out`, nearest first. Missing ranks are filled
+/// with `CSW_NO_LABEL`. Returns 0 on success, and nonzero on failure, including buffer sizes
+/// that overflow `size_t`.
+///
+/// # Safety
+/// `model` must come from `csw_model_load`, `fingerprints` must hold `num_rows * num_bits`
+/// values and `labels_out` must have room for `num_rows * top_n` labels.
+#[no_mangle]
+pub unsafe extern "C" fn csw_transform(
+    model: *const CswModel,
+    fingerprints: *const i64,
+    num_rows: usize,
+    num_bits: usize,
+    top_n: usize,
+    labels_out: *mut i32,
+) -> i32 {
+    let transformed = catch_result(|| {
+        if model.is_null() || fingerprints.is_null() || labels_out.is_null() {
+            return Err(EncoderError::InvalidArgument("Null pointer argument".to_string()));
+        }
+        if top_n == 0 {
+            return Err(EncoderError::InvalidArgument("top_n must be at least 1".to_string()));
+        }
+
+        let num_values = buffer_len(num_rows, num_bits, "num_rows * num_bits")?;
+        let num_labels = buffer_len(num_rows, top_n, "num_rows * top_n")?;
+
+        let input_data = std::slice::from_raw_parts(fingerprints, num_values)
+            .chunks(num_bits.max(1))
+            .map(|row| row.to_vec())
+            .collect::<Vec<Vec<i64>>>();
+        let ranked_labels = (*model).model.transform_top_n(&input_data, top_n)?;
+
+        let labels_out = std::slice::from_raw_parts_mut(labels_out, num_labels);
+        for (row_labels, labels) in labels_out.chunks_mut(top_n).zip(ranked_labels) {
+            row_labels.fill(CSW_NO_LABEL);
+            row_labels[..labels.len()].copy_from_slice(&labels);
+        }
+
+        Ok(())
+    });
+
+    match transformed {
+        Some(()) => 0,
+        None => 1,
+    }
+}
+
+// Length of a caller's `rows * cols` buffer, rejecting sizes no allocation could have
+fn buffer_len(rows: usize, cols: usize, name: &str) -> Result<usize> {
+    rows.checked_mul(cols)
+        .filter(|&len| len <= isize::MAX as usize / 8)
+        .ok_or_else(|| EncoderError::InvalidArgument(format!("{} overflows ({} * {})", name, rows, cols)))
+}
+
+/// Frees a model from `csw_model_load`; null is ignored.
+///
+/// # Safety
+/// `model` must be null or come from `csw_model_load`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn csw_free(model: *mut CswModel) {
+    if !model.is_null() {
+        drop(Box::from_raw(model));
+    }
+}
+
+/// Message of the last failure on the calling thread, or null. Valid until the next call on
+/// this thread; do not free it.
+#[no_mangle]
+pub extern "C" fn csw_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
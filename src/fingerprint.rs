@@ -0,0 +1,152 @@
+use crate::error::{EncoderError, Result};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FingerprintKind {
+    /// RDKit's path-based fingerprint
+    Rdkit,
+    /// Morgan (circular) fingerprint, ECFP-like
+    Morgan,
+    /// RDKit's substructure pattern fingerprint
+    Pattern,
+}
+
+/// The fingerprint an encoder was trained on, as declared under `fingerprint` in the asset
+/// manifest, e.g. `{"kind": "morgan", "radius": 2, "num_bits": 2048}`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FingerprintSpec {
+    pub kind: FingerprintKind,
+    /// Only meaningful for Morgan fingerprints
+    #[serde(default)]
+    pub radius: Option<u32>,
+    pub num_bits: usize,
+}
+
+impl FingerprintSpec {
+    /// Checks every row is a binary fingerprint of `num_bits` bits. Wider rows whose width is a
+    /// multiple of `num_bits` are folded down the way RDKit's `FoldFingerprint` does, OR-ing bit
+    /// `i` with every bit `i + n * num_bits`; rows are only copied when one needs folding.
+    pub fn conform<'a>(&self, input_data: &'a [Vec<i64>]) -> Result<Cow<'a, [Vec<i64>]>> {
+        for (row, fingerprint) in input_data.iter().enumerate() {
+            if let Some(value) = fingerprint.iter().find(|value| **value != 0 && **value != 1) {
+                return Err(EncoderError::InvalidArgument(format!(
+                    "Row {} has bit value {}, but {:?} fingerprints are binary",
+                    row, value, self.kind
+                )));
+            }
+            if fingerprint.len() != self.num_bits && !self.can_fold(fingerprint.len()) {
+                return Err(EncoderError::ShapeMismatch(format!(
+                    "Row {} has {} bits, but the encoder expects a {}-bit {:?} fingerprint or a \
+                     multiple of it to fold",
+                    row,
+                    fingerprint.len(),
+                    self.num_bits,
+                    self.kind
+                )));
+            }
+        }
+
+        if input_data.iter().all(|fingerprint| fingerprint.len() == self.num_bits) {
+            return Ok(Cow::Borrowed(input_data));
+        }
+
+        let folded = input_data
+            .iter()
+            .map(|fingerprint| self.fold(fingerprint))
+            .collect();
+        Ok(Cow::Owned(folded))
+    }
+
+    fn can_fold(&self, bits: usize) -> bool {
+        self.num_bits > 0 && bits > self.num_bits && bits % self.num_bits == 0
+    }
+
+    fn fold(&self, fingerprint: &[i64]) -> Vec<i64> {
+        let mut folded = vec![0; self.num_bits];
+        for (idx, bit) in fingerprint.iter().enumerate() {
+            folded[idx % self.num_bits] |= bit;
+        }
+        folded
+    }
+}
+
+/// Binary fingerprint rows packed 64 bits to a word, bit `i` of a row at bit `i % 64` of word
+/// `i / 64` (RDKit's `ExplicitBitVect` order). Each row takes `num_bits.div_ceil(64)` words;
+/// padding bits past `num_bits` are ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct PackedFingerprints<'a> {
+    words: &'a [u64],
+    num_bits: usize,
+}
+
+impl<'a> PackedFingerprints<'a> {
+    pub fn new(words: &'a [u64], num_bits: usize) -> Result<Self> {
+        if num_bits == 0 {
+            return Err(EncoderError::ShapeMismatch("Packed fingerprints have no bits".to_string()));
+        }
+
+        let words_per_row = num_bits.div_ceil(64);
+        if words.len() % words_per_row != 0 {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} words, which is not a whole number of {}-bit rows of {} words",
+                words.len(),
+                num_bits,
+                words_per_row
+            )));
+        }
+
+        Ok(PackedFingerprints { words, num_bits })
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.words.len() / self.words_per_row()
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u64]> {
+        self.words.chunks(self.words_per_row())
+    }
+
+    /// Row-major `[num_rows, num_bits]` bits, converted to `T` one bit at a time.
+    pub fn unpack_flat<T: From<u8>>(&self) -> Vec<T> {
+        let num_bits = self.num_bits;
+        let mut values = Vec::with_capacity(self.num_rows() * num_bits);
+        for row in self.rows() {
+            values.extend((0..num_bits).map(|bit| T::from(((row[bit / 64] >> (bit % 64)) & 1) as u8)));
+        }
+        values
+    }
+
+    /// One `0`/`1` element per bit, as `transform` takes them.
+    pub fn unpack(&self) -> Vec<Vec<i64>> {
+        self.unpack_flat::<u8>()
+            .chunks(self.num_bits)
+            .map(|row| row.iter().map(|&bit| bit as i64).collect())
+            .collect()
+    }
+
+    fn words_per_row(&self) -> usize {
+        self.num_bits.div_ceil(64)
+    }
+}
+
+/// Packs `0`/`1` rows into words for `PackedFingerprints`; any non-zero element sets its bit.
+pub fn pack_fingerprints(input_data: &[Vec<i64>]) -> Vec<u64> {
+    input_data
+        .iter()
+        .flat_map(|row| {
+            let mut words = vec![0u64; row.len().div_ceil(64)];
+            for (bit, value) in row.iter().enumerate() {
+                if *value != 0 {
+                    words[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+            words
+        })
+        .collect()
+}
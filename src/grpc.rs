@@ -0,0 +1,116 @@
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use proto::cluster_assignment_server::{ClusterAssignment, ClusterAssignmentServer};
+use proto::{AssignRequest, AssignResponse, Assignment};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Bounds responses buffered per stream before the server stops reading requests
+const RESPONSE_BUFFER: usize = 4;
+
+pub mod proto {
+    tonic::include_proto!("cheminee.similarity.v1");
+}
+
+pub struct AssignService {
+    model: Arc<EncoderModel>,
+}
+
+impl AssignService {
+    pub fn new(model: Arc<EncoderModel>) -> Self {
+        AssignService { model }
+    }
+
+    pub fn into_server(self) -> ClusterAssignmentServer<Self> {
+        ClusterAssignmentServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl ClusterAssignment for AssignService {
+    type AssignStream = ReceiverStream<std::result::Result<AssignResponse, Status>>;
+
+    async fn assign(
+        &self,
+        request: Request<Streaming<AssignRequest>>,
+    ) -> std::result::Result<Response<Self::AssignStream>, Status> {
+        let mut requests = request.into_inner();
+        let (sender, receiver) = mpsc::channel(RESPONSE_BUFFER);
+        let model = self.model.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let response = match requests.message().await {
+                    Ok(Some(request)) => assign_request(model.clone(), request).await,
+                    Ok(None) => break,
+                    Err(status) => Err(status),
+                };
+
+                let failed = response.is_err();
+                if sender.send(response).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+async fn assign_request(model: Arc<EncoderModel>, request: AssignRequest) -> std::result::Result<AssignResponse, Status> {
+    if request.fingerprints.is_empty() {
+        return Ok(AssignResponse { assignments: Vec::new() });
+    }
+
+    let top_n = request.top_n.max(1) as usize;
+    let rows = request
+        .fingerprints
+        .into_iter()
+        .map(|fingerprint| fingerprint.bits)
+        .collect::<Vec<Vec<i64>>>();
+
+    let assignments = tokio::task::spawn_blocking(move || model.transform_assignments(&rows, top_n))
+        .await
+        .map_err(|e| Status::internal(e.to_string()))??;
+
+    let assignments = assignments
+        .into_iter()
+        .map(|assignment| Assignment {
+            labels: assignment.labels,
+            distances: assignment.distances,
+        })
+        .collect();
+
+    Ok(AssignResponse { assignments })
+}
+
+pub async fn serve_grpc(model: Arc<EncoderModel>, addr: SocketAddr) -> Result<()> {
+    tracing::info!("Serving gRPC cluster assignment on {}", addr);
+
+    tonic::transport::Server::builder()
+        .add_service(AssignService::new(model).into_server())
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
+
+impl From<EncoderError> for Status {
+    fn from(e: EncoderError) -> Self {
+        match e {
+            EncoderError::ShapeMismatch(_) | EncoderError::InvalidArgument(_) | EncoderError::NanLatent { .. } => {
+                Status::invalid_argument(e.to_string())
+            }
+            _ => Status::internal(e.to_string()),
+        }
+    }
+}
+
+impl From<tonic::transport::Error> for EncoderError {
+    fn from(e: tonic::transport::Error) -> Self {
+        EncoderError::Io(std::io::Error::other(e))
+    }
+}
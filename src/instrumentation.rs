@@ -0,0 +1,62 @@
+use crate::error::Result;
+use std::time::Instant;
+
+/// Fingerprints run through the encoder
+pub const INFERENCES_TOTAL: &str = "cheminee_similarity_inferences_total";
+/// Rows per encoder call
+pub const BATCH_SIZE: &str = "cheminee_similarity_batch_size";
+pub const ENCODE_SECONDS: &str = "cheminee_similarity_encode_seconds";
+pub const ASSIGN_SECONDS: &str = "cheminee_similarity_assign_seconds";
+/// Failed encoder or assignment calls, labelled with `stage`
+pub const ERRORS_TOTAL: &str = "cheminee_similarity_errors_total";
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Stage {
+    Encode,
+    Assign,
+}
+
+impl Stage {
+    #[cfg(feature = "metrics")]
+    fn label(self) -> &'static str {
+        match self {
+            Stage::Encode => "encode",
+            Stage::Assign => "assign",
+        }
+    }
+}
+
+/// Registers units and help text for every metric with the installed recorder. Optional, but
+/// exporters show the descriptions once it has run.
+#[cfg(feature = "metrics")]
+pub fn describe_metrics() {
+    use metrics::{describe_counter, describe_histogram, Unit};
+
+    describe_counter!(INFERENCES_TOTAL, Unit::Count, "Fingerprints run through the encoder");
+    describe_histogram!(BATCH_SIZE, Unit::Count, "Rows per encoder call");
+    describe_histogram!(ENCODE_SECONDS, Unit::Seconds, "Encoder call latency");
+    describe_histogram!(ASSIGN_SECONDS, Unit::Seconds, "Cluster assignment latency");
+    describe_counter!(ERRORS_TOTAL, Unit::Count, "Failed encoder or assignment calls by stage");
+}
+
+// Records one encoder or assignment call over `rows` rows that began at `started`
+#[cfg(feature = "metrics")]
+pub(crate) fn record<T>(stage: Stage, rows: usize, started: Instant, result: &Result<T>) {
+    let elapsed = started.elapsed().as_secs_f64();
+
+    match stage {
+        Stage::Encode => {
+            metrics::counter!(INFERENCES_TOTAL).increment(rows as u64);
+            metrics::histogram!(BATCH_SIZE).record(rows as f64);
+            metrics::histogram!(ENCODE_SECONDS).record(elapsed);
+        },
+        Stage::Assign => metrics::histogram!(ASSIGN_SECONDS).record(elapsed),
+    }
+
+    if result.is_err() {
+        metrics::counter!(ERRORS_TOTAL, "stage" => stage.label()).increment(1);
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record<T>(_stage: Stage, _rows: usize, _started: Instant, _result: &Result<T>) {}
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Maps a centroid distance to an (unnormalized) cluster weight.
+#[derive(Clone)]
+pub enum WeightKernel {
+    /// `exp(-d / temperature)`
+    Exponential { temperature: f32 },
+    /// `exp(-d^2 / (2 * bandwidth^2))`
+    Gaussian { bandwidth: f32 },
+    /// `1 / (d + epsilon)`
+    InverseDistance { epsilon: f32 },
+    /// Arbitrary weighting function; need not decrease with distance
+    Custom(Arc<dyn Fn(f32) -> f32 + Send + Sync>),
+}
+
+impl WeightKernel {
+    pub fn weight(&self, distance: f32) -> f32 {
+        match self {
+            WeightKernel::Exponential { temperature } => (-distance / temperature).exp(),
+            WeightKernel::Gaussian { bandwidth } => {
+                (-(distance * distance) / (2.0 * bandwidth * bandwidth)).exp()
+            }
+            WeightKernel::InverseDistance { epsilon } => 1.0 / (distance + epsilon),
+            WeightKernel::Custom(kernel_fn) => kernel_fn(distance),
+        }
+    }
+}
+
+/// Post-processing applied to every computed weight to keep weight vectors free of denormals.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WeightClamp {
+    /// Weights below this become exactly zero
+    pub floor: Option<f32>,
+    /// Round weights to this many decimal places
+    pub precision: Option<u32>,
+}
+
+impl WeightClamp {
+    pub fn apply(&self, weight: f32) -> f32 {
+        if self.floor.is_some_and(|floor| weight < floor) {
+            return 0.0;
+        }
+
+        match self.precision {
+            Some(precision) => {
+                let scale = 10f32.powi(precision as i32);
+                (weight * scale).round() / scale
+            }
+            None => weight,
+        }
+    }
+}
+
+impl Default for WeightKernel {
+    fn default() -> Self {
+        WeightKernel::Exponential { temperature: 1.0 }
+    }
+}
+
+impl fmt::Debug for WeightKernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightKernel::Exponential { temperature } => f
+                .debug_struct("Exponential")
+                .field("temperature", temperature)
+                .finish(),
+            WeightKernel::Gaussian { bandwidth } => f
+                .debug_struct("Gaussian")
+                .field("bandwidth", bandwidth)
+                .finish(),
+            WeightKernel::InverseDistance { epsilon } => f
+                .debug_struct("InverseDistance")
+                .field("epsilon", epsilon)
+                .finish(),
+            WeightKernel::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::centroids::{row_major, Centroids};
+use crate::error::{EncoderError, Result};
+use crate::metric::DistanceMetric;
+use ndarray::Array2;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct KMeansConfig {
+    pub k: usize,
+    pub max_iterations: usize,
+    /// Stop once no centroid moves farther than this RMS distance in an iteration
+    pub tolerance: f32,
+    /// Seeds k-means++ initialization; the same seed and corpus always give the same centroids
+    pub seed: u64,
+}
+
+impl Default for KMeansConfig {
+    fn default() -> Self {
+        KMeansConfig {
+            k: 10_000,
+            max_iterations: 100,
+            tolerance: 1e-4,
+            seed: 0,
+        }
+    }
+}
+
+pub struct KMeansFit {
+    /// Fitted centroids, with each radius set to the RMS distance of the cluster's farthest member
+    pub centroids: Centroids,
+    /// Sum of squared Euclidean distances from each latent to its centroid
+    pub inertia: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Fits `config.k` centroids to `[rows, latent_dim]` latents with k-means++ initialization and
+/// Lloyd iterations. Distances are computed in parallel with the `rayon` feature. Clusters left
+/// empty by an iteration are reseeded with the latent farthest from its centroid.
+pub fn fit_kmeans(latents: &Array2<f32>, config: &KMeansConfig) -> Result<KMeansFit> {
+    let (num_rows, dim) = latents.dim();
+    if config.k == 0 || config.k > num_rows {
+        return Err(EncoderError::InvalidArgument(format!(
+            "k must be in 1..={} for {} latents, got {}",
+            num_rows,
+            num_rows,
+            config.k
+        )));
+    }
+    if dim == 0 {
+        return Err(EncoderError::ShapeMismatch("Latents must have at least one column".to_string()));
+    }
+
+    let latents = latents.iter().copied().collect::<Vec<f32>>();
+    if latents.iter().any(|value| !value.is_finite()) {
+        return Err(EncoderError::InvalidArgument("Latents must be finite to fit centroids".to_string()));
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut centroids = kmeans_plus_plus(&latents, dim, config.k, &mut rng);
+
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut assignments = nearest_centroids(&latents, &centroids, dim);
+
+    while iterations < config.max_iterations {
+        iterations += 1;
+
+        let updated_centroids = update_centroids(&latents, &assignments, dim, config.k);
+        let max_shift = centroids
+            .chunks(dim)
+            .zip(updated_centroids.chunks(dim))
+            .map(|(old, new)| DistanceMetric::Euclidean.distance(old, new))
+            .fold(0.0f32, f32::max);
+
+        centroids = updated_centroids;
+        assignments = nearest_centroids(&latents, &centroids, dim);
+
+        if max_shift <= config.tolerance {
+            converged = true;
+            break;
+        }
+    }
+
+    let inertia = assignments.iter().map(|(_, squared_distance)| *squared_distance as f64).sum();
+
+    let mut radii = vec![0.0f32; config.k];
+    for (latent, (cluster, _)) in latents.chunks(dim).zip(&assignments) {
+        let distance = DistanceMetric::Euclidean.distance(latent, &centroids[cluster * dim..(cluster + 1) * dim]);
+        radii[*cluster] = radii[*cluster].max(distance);
+    }
+
+    tracing::info!(
+        "Fitted {} centroids to {} latents in {} iterations (converged: {}, inertia: {})",
+        config.k,
+        num_rows,
+        iterations,
+        converged,
+        inertia
+    );
+
+    Ok(KMeansFit {
+        centroids: Centroids {
+            coordinates: Array2::from_shape_vec((config.k, dim), centroids)?,
+            radii: Some(radii),
+        },
+        inertia,
+        iterations,
+        converged,
+    })
+}
+
+/// Mini-batch k-means for drifting data: each `partial_fit` nudges the centroids toward the mean
+/// of the latents assigned to them, `c <- c + learning_rate * (batch_mean - c)`. Clusters without
+/// members in a batch stay put, and radii are carried over from the starting centroids unchanged.
+pub struct MiniBatchKMeans {
+    centroids: Vec<f32>,
+    radii: Option<Vec<f32>>,
+    dim: usize,
+    learning_rate: f32,
+}
+
+impl MiniBatchKMeans {
+    pub fn new(centroids: &Centroids, learning_rate: f32) -> Result<Self> {
+        if !(learning_rate > 0.0 && learning_rate <= 1.0) {
+            return Err(EncoderError::InvalidArgument(format!(
+                "Learning rate must be in (0, 1], got {}",
+                learning_rate
+            )));
+        }
+
+        Ok(MiniBatchKMeans {
+            centroids: row_major(&centroids.coordinates).into_owned(),
+            radii: centroids.radii.clone(),
+            dim: centroids.dim(),
+            learning_rate,
+        })
+    }
+
+    /// Updates the centroids from one `[rows, latent_dim]` batch and returns the largest RMS
+    /// distance any centroid moved.
+    pub fn partial_fit(&mut self, latents: &Array2<f32>) -> Result<f32> {
+        if latents.ncols() != self.dim {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Centroids have width {}, but latents have width {}",
+                self.dim,
+                latents.ncols()
+            )));
+        }
+
+        let latents = latents.iter().copied().collect::<Vec<f32>>();
+        if latents.iter().any(|value| !value.is_finite()) {
+            return Err(EncoderError::InvalidArgument("Latents must be finite to update centroids".to_string()));
+        }
+
+        let num_clusters = self.centroids.len() / self.dim;
+        let assignments = nearest_centroids(&latents, &self.centroids, self.dim);
+
+        let mut sums = vec![0.0f64; self.centroids.len()];
+        let mut counts = vec![0usize; num_clusters];
+        for (latent, (cluster, _)) in latents.chunks(self.dim).zip(&assignments) {
+            counts[*cluster] += 1;
+            for (sum, value) in sums[cluster * self.dim..(cluster + 1) * self.dim].iter_mut().zip(latent) {
+                *sum += *value as f64;
+            }
+        }
+
+        let mut max_shift = 0.0f32;
+        for (cluster, &count) in counts.iter().enumerate().filter(|&(_, &count)| count > 0) {
+            let centroid = &mut self.centroids[cluster * self.dim..(cluster + 1) * self.dim];
+            let previous = centroid.to_vec();
+
+            for (value, sum) in centroid.iter_mut().zip(&sums[cluster * self.dim..(cluster + 1) * self.dim]) {
+                let batch_mean = (sum / count as f64) as f32;
+                *value += self.learning_rate * (batch_mean - *value);
+            }
+
+            max_shift = max_shift.max(DistanceMetric::Euclidean.distance(&previous, centroid));
+        }
+
+        Ok(max_shift)
+    }
+
+    /// The current centroids, ready for `EncoderModel::update_centroids` or `save_centroids_csv`.
+    pub fn centroids(&self) -> Result<Centroids> {
+        let num_clusters = self.centroids.len() / self.dim;
+
+        Ok(Centroids {
+            coordinates: Array2::from_shape_vec((num_clusters, self.dim), self.centroids.clone())?,
+            radii: self.radii.clone(),
+        })
+    }
+}
+
+// Each further centroid is drawn with probability proportional to its squared distance from the
+// nearest centroid chosen so far
+fn kmeans_plus_plus(latents: &[f32], dim: usize, k: usize, rng: &mut StdRng) -> Vec<f32> {
+    let num_rows = latents.len() / dim;
+    let first = rng.gen_range(0..num_rows);
+
+    let mut centroids = Vec::with_capacity(k * dim);
+    centroids.extend_from_slice(&latents[first * dim..(first + 1) * dim]);
+
+    let mut min_distances = squared_distances_to(latents, &centroids[..dim], dim);
+
+    for _ in 1..k {
+        let next = match WeightedIndex::new(&min_distances) {
+            Ok(weights) => weights.sample(rng),
+            // Every latent already coincides with a centroid, so any row will do
+            Err(_) => rng.gen_range(0..num_rows),
+        };
+
+        let centroid = &latents[next * dim..(next + 1) * dim];
+        centroids.extend_from_slice(centroid);
+
+        let distances = squared_distances_to(latents, centroid, dim);
+        for (min_distance, distance) in min_distances.iter_mut().zip(distances) {
+            *min_distance = min_distance.min(distance);
+        }
+    }
+
+    centroids
+}
+
+fn update_centroids(latents: &[f32], assignments: &[(usize, f32)], dim: usize, k: usize) -> Vec<f32> {
+    let mut sums = vec![0.0f64; k * dim];
+    let mut counts = vec![0usize; k];
+
+    for (latent, (cluster, _)) in latents.chunks(dim).zip(assignments) {
+        counts[*cluster] += 1;
+        for (sum, value) in sums[cluster * dim..(cluster + 1) * dim].iter_mut().zip(latent) {
+            *sum += *value as f64;
+        }
+    }
+
+    let mut centroids = sums
+        .chunks(dim)
+        .zip(&counts)
+        .flat_map(|(sum, &count)| sum.iter().map(move |value| (value / count.max(1) as f64) as f32))
+        .collect::<Vec<f32>>();
+
+    // Reseed empty clusters with the latents farthest from their centroids, one latent each
+    let mut farthest = (0..assignments.len()).collect::<Vec<usize>>();
+    farthest.sort_by(|&a, &b| assignments[b].1.total_cmp(&assignments[a].1));
+    let mut farthest = farthest.into_iter();
+
+    for cluster in (0..k).filter(|&cluster| counts[cluster] == 0) {
+        if let Some(row) = farthest.next() {
+            centroids[cluster * dim..(cluster + 1) * dim].copy_from_slice(&latents[row * dim..(row + 1) * dim]);
+        }
+    }
+
+    centroids
+}
+
+// Nearest centroid index and squared Euclidean distance for every latent row
+fn nearest_centroids(latents: &[f32], centroids: &[f32], dim: usize) -> Vec<(usize, f32)> {
+    let nearest = |latent: &[f32]| {
+        centroids
+            .chunks(dim)
+            .map(|centroid| squared_distance(latent, centroid))
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((0, 0.0))
+    };
+
+    #[cfg(feature = "rayon")]
+    let assignments = latents.par_chunks(dim).map(nearest).collect();
+    #[cfg(not(feature = "rayon"))]
+    let assignments = latents.chunks(dim).map(nearest).collect();
+
+    assignments
+}
+
+fn squared_distances_to(latents: &[f32], centroid: &[f32], dim: usize) -> Vec<f32> {
+    #[cfg(feature = "rayon")]
+    let distances = latents.par_chunks(dim).map(|latent| squared_distance(latent, centroid)).collect();
+    #[cfg(not(feature = "rayon"))]
+    let distances = latents.chunks(dim).map(|latent| squared_distance(latent, centroid)).collect();
+
+    distances
+}
+
+fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
@@ -0,0 +1,170 @@
+use crate::ann::{CentroidIndex, HnswConfig};
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use crate::metric::DistanceMetric;
+use crate::npy::NpyWriter;
+use ndarray::Array2;
+use ndarray_npy::{read_npy, ReadNpyError};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+pub const LATENTS_FILE: &str = "latents.npy";
+pub const IDS_FILE: &str = "ids.txt";
+
+/// Nearest-neighbour index over an arbitrary corpus of latent vectors, keyed by caller IDs.
+/// Saved as a directory of `latents.npy` and one ID per line in `ids.txt`; the HNSW graph is
+/// rebuilt on load, so the metric and `HnswConfig` can change between saves and loads.
+pub struct LatentIndex {
+    ids: Vec<String>,
+    latents: Vec<f32>,
+    dim: usize,
+    index: CentroidIndex,
+}
+
+impl LatentIndex {
+    /// `latents` holds one row-major row of width `dim` per ID.
+    pub fn build(
+        ids: Vec<String>,
+        latents: Vec<f32>,
+        dim: usize,
+        config: &HnswConfig,
+        metric: DistanceMetric,
+    ) -> Result<Self> {
+        if dim == 0 || latents.len() != ids.len() * dim {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} latent values for {} IDs of width {}",
+                latents.len(),
+                ids.len(),
+                dim
+            )));
+        }
+        if ids.iter().any(|id| id.contains('\n')) {
+            return Err(EncoderError::InvalidArgument("Latent index IDs can't contain newlines".to_string()));
+        }
+
+        let index = CentroidIndex::build_from_rows(&latents, dim, config, metric);
+        tracing::info!("Built latent index over {} vectors of width {}", ids.len(), dim);
+
+        Ok(LatentIndex {
+            ids,
+            latents,
+            dim,
+            index,
+        })
+    }
+
+    pub fn load(dir: &str, config: &HnswConfig, metric: DistanceMetric) -> Result<Self> {
+        let latents_path = Path::new(dir).join(LATENTS_FILE);
+        let ids_path = Path::new(dir).join(IDS_FILE);
+        for path in [&latents_path, &ids_path] {
+            if !path.is_file() {
+                return Err(EncoderError::MissingAssets(format!("Latent index file {} not found", path.display())));
+            }
+        }
+
+        let matrix = match read_npy::<_, Array2<f32>>(&latents_path) {
+            Err(ReadNpyError::WrongDescriptor(_)) => {
+                read_npy::<_, Array2<f64>>(&latents_path).map(|matrix| matrix.mapv(|value| value as f32))
+            }
+            matrix => matrix,
+        }
+        .map_err(|e| EncoderError::Format(format!("Failed to read {}: {}", latents_path.display(), e)))?;
+
+        let ids = BufReader::new(File::open(&ids_path)?)
+            .lines()
+            .collect::<std::io::Result<Vec<String>>>()?;
+
+        let dim = matrix.ncols();
+        let latents = matrix.into_iter().collect();
+        Self::build(ids, latents, dim, config, metric)
+    }
+
+    /// Writes the IDs and latents into `dir`, creating it if needed.
+    pub fn save(&self, dir: &str) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+
+        let latents_path = Path::new(dir).join(LATENTS_FILE);
+        let mut writer = NpyWriter::create(&latents_path.to_string_lossy(), self.dim)?;
+        writer.write_rows(&self.latents)?;
+        writer.finish()?;
+
+        let mut ids_file = BufWriter::new(File::create(Path::new(dir).join(IDS_FILE))?);
+        for id in &self.ids {
+            writeln!(ids_file, "{}", id)?;
+        }
+        ids_file.flush()?;
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Up to `k` `(id, distance)` pairs nearest to `latent`, closest first.
+    pub fn search(&self, latent: &[f32], k: usize) -> Result<Vec<(String, f32)>> {
+        if latent.len() != self.dim {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Query latent has width {}, but the index holds width {}",
+                latent.len(),
+                self.dim
+            )));
+        }
+
+        let neighbours = self
+            .index
+            .search(latent, k)
+            .into_iter()
+            .map(|(row, distance)| (self.ids[row as usize].clone(), distance))
+            .collect();
+        Ok(neighbours)
+    }
+}
+
+impl EncoderModel {
+    /// Encodes `input_data` and builds a `LatentIndex` over it under the model's metric, one ID
+    /// per fingerprint.
+    pub fn build_latent_index(
+        &self,
+        ids: Vec<String>,
+        input_data: &[Vec<i64>],
+        config: &HnswConfig,
+    ) -> Result<LatentIndex> {
+        if ids.len() != input_data.len() {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} IDs for {} fingerprints",
+                ids.len(),
+                input_data.len()
+            )));
+        }
+
+        let latents = self.encode_latent(input_data)?;
+        let dim = latents.ncols();
+        LatentIndex::build(ids, latents.into_iter().collect(), dim, config, self.config().metric)
+    }
+
+    /// Top `k` `(id, distance)` neighbours in `index` for each fingerprint.
+    pub fn nearest_neighbors(
+        &self,
+        index: &LatentIndex,
+        input_data: &[Vec<i64>],
+        k: usize,
+    ) -> Result<Vec<Vec<(String, f32)>>> {
+        let latents = self.encode_latent(input_data)?;
+
+        latents
+            .rows()
+            .into_iter()
+            .map(|latent| index.search(&latent.to_vec(), k))
+            .collect()
+    }
+}
@@ -1 +1,68 @@
+#[cfg(all(feature = "model", not(any(feature = "tensorflow", feature = "onnx", feature = "tract", feature = "candle"))))]
+compile_error!("`EncoderModel` needs an encoder backend: enable `tensorflow`, `onnx`, `tract` or `candle`");
+
+#[cfg(feature = "model")]
+pub mod ann;
+#[cfg(feature = "model")]
+mod archive;
+#[cfg(feature = "model")]
+mod assignment;
+#[cfg(feature = "async")]
+mod async_transform;
+#[cfg(feature = "model")]
+mod backend;
+#[cfg(feature = "model")]
+pub mod builder;
+pub mod cancel;
+#[cfg(feature = "model")]
+pub mod centroids;
+#[cfg(feature = "model")]
+pub mod config;
+#[cfg(feature = "model")]
+pub mod covariance;
+#[cfg(any(feature = "model", feature = "wasm"))]
+mod csv_rows;
+#[cfg(feature = "tensorflow")]
+pub mod decoder;
+#[cfg(feature = "download")]
+pub mod download;
+#[cfg(feature = "model")]
 pub mod encoder;
+pub mod error;
+#[cfg(feature = "model")]
+pub mod ffi;
+pub mod fingerprint;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod kernel;
+#[cfg(feature = "model")]
+pub mod instrumentation;
+#[cfg(feature = "model")]
+pub mod kmeans;
+#[cfg(feature = "model")]
+pub mod latent_index;
+pub mod manifest;
+pub mod metric;
+pub mod npy;
+pub mod outlier;
+#[cfg(feature = "parquet")]
+pub mod parquet_io;
+#[cfg(feature = "model")]
+pub mod pool;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
+#[cfg(feature = "model")]
+pub mod registry;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "rdkit")]
+pub mod smiles;
+#[cfg(feature = "tensorflow")]
+pub mod session;
+pub mod stats;
+#[cfg(feature = "tokio")]
+mod stream;
+#[cfg(feature = "wasm")]
+pub mod wasm;
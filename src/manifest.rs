@@ -0,0 +1,43 @@
+use crate::error::{EncoderError, Result};
+use crate::fingerprint::FingerprintSpec;
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::Path;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Release versions and the expected input fingerprint, shipped as `manifest.json` in the
+/// assets directory.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct AssetManifest {
+    pub model_version: Option<String>,
+    pub centroid_version: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<FingerprintSpec>,
+}
+
+/// Reads the manifest in `assets_path`, if there is one.
+pub fn load_asset_manifest(assets_path: &str) -> Result<Option<AssetManifest>> {
+    let manifest_path = Path::new(assets_path).join(MANIFEST_FILE);
+    if !manifest_path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = read_to_string(&manifest_path)?;
+    let manifest = serde_json::from_str(&contents).map_err(|e| {
+        EncoderError::Manifest(format!("Failed to parse asset manifest {}: {}", manifest_path.display(), e))
+    })?;
+
+    Ok(Some(manifest))
+}
+
+/// The last `YYYYMMDD` stamp in a file name, e.g. `20241111` for
+/// `lf_kmeans_10k_centroids_20241111.csv`.
+pub fn date_stamp(file_name: &str) -> Option<String> {
+    let stem = Path::new(file_name).file_stem()?.to_string_lossy();
+
+    stem.split(|c: char| !c.is_ascii_digit())
+        .filter(|digits| digits.len() == 8)
+        .last()
+        .map(str::to_string)
+}
@@ -0,0 +1,95 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Distance used to compare latent vectors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// Root-mean-square difference across latent dimensions, as used for centroid assignment
+    #[default]
+    Euclidean,
+    /// One minus the cosine similarity, in `[0, 2]`; a zero vector is at distance 1 from everything
+    Cosine,
+    /// Mean absolute difference across latent dimensions
+    Manhattan,
+    /// RMS distance scaled by each cluster's covariance from `cluster_covariance_file`, see
+    /// `ClusterCovariance::distance`. Without a cluster to take the covariance from, e.g. between
+    /// two latents, on the coarse set or in the HNSW index, it is plain `Euclidean`.
+    Mahalanobis,
+}
+
+impl DistanceMetric {
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::Euclidean | DistanceMetric::Mahalanobis => {
+                let squared_sum = a
+                    .iter()
+                    .zip(b)
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f32>();
+
+                (squared_sum / a.len() as f32).sqrt()
+            }
+            DistanceMetric::Cosine => {
+                let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+                let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+
+                if norms == 0.0 {
+                    1.0
+                } else {
+                    (1.0 - dot / norms).max(0.0)
+                }
+            }
+            DistanceMetric::Manhattan => {
+                let absolute_sum = a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum::<f32>();
+                absolute_sum / a.len() as f32
+            }
+        }
+    }
+
+    /// Index and distance of the candidate closest to `query`, skipping `NaN` distances.
+    pub fn nearest<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a [f32]>,
+        query: &[f32],
+    ) -> Option<(usize, f32)> {
+        candidates
+            .into_iter()
+            .map(|candidate| self.distance(candidate, query))
+            .enumerate()
+            .filter(|(_, distance)| !distance.is_nan())
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Nearest `top_n` centroids (all when `None`) as `(cluster_id, distance)` pairs, nearest
+    /// first, for every `dim`-wide row of `latents`. Both inputs are flat row-major buffers.
+    pub fn rank(&self, latents: &[f32], centroids: &[f32], dim: usize, top_n: Option<usize>) -> Vec<Vec<(i32, f32)>> {
+        let rank_row = |latent: &[f32]| {
+            let mut ranked_clusters = centroids
+                .chunks(dim)
+                .enumerate()
+                .map(|(idx, centroid)| (idx as i32, self.distance(centroid, latent)))
+                .collect::<Vec<(i32, f32)>>();
+
+            let k = top_n.unwrap_or(ranked_clusters.len()).min(ranked_clusters.len());
+            if k == 0 {
+                return vec![];
+            }
+
+            // Partition out the nearest `k` before sorting only those
+            if k < ranked_clusters.len() {
+                ranked_clusters.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+                ranked_clusters.truncate(k);
+            }
+
+            ranked_clusters.sort_by(|a, b| a.1.total_cmp(&b.1));
+            ranked_clusters
+        };
+
+        #[cfg(feature = "rayon")]
+        let ranked_clusters = latents.par_chunks(dim).map(rank_row).collect();
+        #[cfg(not(feature = "rayon"))]
+        let ranked_clusters = latents.chunks(dim).map(rank_row).collect();
+
+        ranked_clusters
+    }
+}
@@ -0,0 +1,76 @@
+use crate::error::{EncoderError, Result};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+
+const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+// Magic, version and header length plus a header dict padded to this many bytes, enough for any
+// u64 shape so the row count can be rewritten in place once streaming is done
+const HEADER_SIZE: usize = 128;
+
+/// Streams `[rows, dim]` little-endian f32 rows into a `.npy` file without knowing the row count
+/// up front. The header is written with a placeholder shape and patched in `finish`.
+pub struct NpyWriter {
+    file: BufWriter<File>,
+    dim: usize,
+    rows: usize,
+}
+
+impl NpyWriter {
+    pub fn create(path: &str, dim: usize) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&npy_header(0, dim)?)?;
+
+        Ok(NpyWriter { file, dim, rows: 0 })
+    }
+
+    /// Appends row-major values; the length must be a multiple of `dim`.
+    pub fn write_rows(&mut self, values: &[f32]) -> Result<()> {
+        if values.len() % self.dim != 0 {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "Got {} values, which is not a whole number of rows of width {}",
+                values.len(),
+                self.dim
+            )));
+        }
+
+        for value in values {
+            self.file.write_all(&value.to_le_bytes())?;
+        }
+        self.rows += values.len() / self.dim;
+
+        Ok(())
+    }
+
+    /// Rewrites the header with the final row count and returns it.
+    pub fn finish(mut self) -> Result<usize> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&npy_header(self.rows, self.dim)?)?;
+        self.file.flush()?;
+
+        Ok(self.rows)
+    }
+}
+
+fn npy_header(rows: usize, dim: usize) -> Result<Vec<u8>> {
+    let dict = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+        rows, dim
+    );
+
+    let dict_size = HEADER_SIZE - MAGIC.len() - 2;
+    if dict.len() + 1 > dict_size {
+        return Err(EncoderError::ShapeMismatch(format!(
+            "npy header for shape ({}, {}) does not fit",
+            rows, dim
+        )));
+    }
+
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&(dict_size as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(HEADER_SIZE - 1, b' ');
+    header.push(b'\n');
+
+    Ok(header)
+}
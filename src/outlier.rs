@@ -0,0 +1,87 @@
+use crate::error::{EncoderError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Per-signal limits for flagging out-of-distribution queries; unset limits are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct OutlierThresholds {
+    /// Flag queries whose nearest centroid is farther than this
+    pub max_nearest_distance: Option<f32>,
+    /// Flag queries whose top-2 centroid distances are closer together than this
+    pub min_margin: Option<f32>,
+    /// Flag queries the decoder reconstructs worse than this, in mean per-bit binary cross-entropy.
+    /// Only checked when scores come from `EncoderModel::outlier_scores_with_decoder`.
+    pub max_reconstruction_error: Option<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutlierScores {
+    pub nearest_distance: f32,
+    pub margin: f32,
+    /// `None` unless a decoder was given to score the query
+    #[serde(default)]
+    pub reconstruction_error: Option<f32>,
+}
+
+impl OutlierThresholds {
+    pub fn is_outlier(&self, scores: &OutlierScores) -> bool {
+        let too_far = self
+            .max_nearest_distance
+            .is_some_and(|max_distance| scores.nearest_distance > max_distance);
+
+        let too_ambiguous = self
+            .min_margin
+            .is_some_and(|min_margin| scores.margin < min_margin);
+
+        let badly_reconstructed = self
+            .max_reconstruction_error
+            .zip(scores.reconstruction_error)
+            .is_some_and(|(max_error, error)| error > max_error);
+
+        too_far || too_ambiguous || badly_reconstructed
+    }
+}
+
+pub fn outlier_scores(ranked_clusters: &[(i32, f32)]) -> Result<OutlierScores> {
+    let nearest_distance = ranked_clusters
+        .first()
+        .map(|(_, distance)| *distance)
+        .ok_or(EncoderError::ShapeMismatch("No cluster distances available".to_string()))?;
+
+    let margin = ranked_clusters
+        .get(1)
+        .map(|(_, distance)| distance - nearest_distance)
+        .unwrap_or(f32::INFINITY);
+
+    Ok(OutlierScores {
+        nearest_distance,
+        margin,
+        reconstruction_error: None,
+    })
+}
+
+/// Mean binary cross-entropy between a fingerprint's bits and the decoder's per-bit probabilities
+/// for it, so inputs far from anything the VAE was trained on score high.
+pub fn reconstruction_error(input: &[i64], probabilities: &[f32]) -> Result<f32> {
+    if input.is_empty() || input.len() != probabilities.len() {
+        return Err(EncoderError::ShapeMismatch(format!(
+            "Cannot score a {}-bit fingerprint against {} decoded probabilities",
+            input.len(),
+            probabilities.len()
+        )));
+    }
+
+    let total = input
+        .iter()
+        .zip(probabilities)
+        .map(|(&bit, &probability)| {
+            let probability = probability.clamp(f32::EPSILON, 1.0 - f32::EPSILON);
+            if bit != 0 {
+                -probability.ln()
+            } else {
+                -(1.0 - probability).ln()
+            }
+        })
+        .sum::<f32>();
+
+    Ok(total / input.len() as f32)
+}
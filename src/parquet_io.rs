@@ -0,0 +1,117 @@
+use crate::centroids::row_major;
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use crate::record_batch::{
+    assignment_columns, fingerprint_rows, latent_column, list_field, DISTANCES_COLUMN, LABELS_COLUMN, LATENT_COLUMN,
+};
+use arrow::array::ArrayRef;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use std::fs::File;
+use std::sync::Arc;
+
+/// Options for `transform_parquet`.
+#[derive(Clone, Debug)]
+pub struct ParquetTransformOptions {
+    /// List or fixed-size-list column of fingerprint bits
+    pub fingerprint_column: String,
+    /// Nearest clusters written per row
+    pub top_k: usize,
+    /// Rows read, encoded and written at a time
+    pub batch_size: usize,
+    /// Also write each row's latent vector
+    pub write_latents: bool,
+}
+
+impl Default for ParquetTransformOptions {
+    fn default() -> Self {
+        ParquetTransformOptions {
+            fingerprint_column: "fingerprint".to_string(),
+            top_k: 1,
+            batch_size: 8192,
+            write_latents: false,
+        }
+    }
+}
+
+impl EncoderModel {
+    /// Streams fingerprints from the Parquet file at `input_path` through the encoder one batch
+    /// at a time and writes `output_path` with every other input column passed through, plus
+    /// `cluster_labels` and `cluster_distances` (`top_k` wide) and, optionally, `latent`.
+    /// Returns the number of rows written.
+    pub fn transform_parquet(&self, input_path: &str, output_path: &str, options: &ParquetTransformOptions) -> Result<usize> {
+        if options.top_k == 0 {
+            return Err(EncoderError::InvalidArgument("top_k must be positive".to_string()));
+        }
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(input_path)?)?
+            .with_batch_size(options.batch_size.max(1))
+            .build()?;
+
+        let mut writer: Option<ArrowWriter<File>> = None;
+        let mut rows_written = 0;
+
+        for batch in reader {
+            let batch = batch?;
+            if batch.num_rows() == 0 {
+                continue;
+            }
+
+            let output_batch = self.parquet_output_batch(&batch, options)?;
+            if writer.is_none() {
+                writer = Some(ArrowWriter::try_new(File::create(output_path)?, output_batch.schema(), None)?);
+            }
+            if let Some(writer) = writer.as_mut() {
+                writer.write(&output_batch)?;
+            }
+
+            rows_written += output_batch.num_rows();
+        }
+
+        match writer {
+            Some(writer) => {
+                writer.close()?;
+            }
+            None => tracing::info!("{} has no rows; nothing written to {}", input_path, output_path),
+        }
+
+        Ok(rows_written)
+    }
+
+    fn parquet_output_batch(&self, batch: &RecordBatch, options: &ParquetTransformOptions) -> Result<RecordBatch> {
+        let fingerprints = fingerprint_rows(batch, &options.fingerprint_column)?;
+        let (lf_array, ranked_clusters) = self.rank_top_clusters_with_latents(&fingerprints, Some(options.top_k))?;
+
+        let schema = batch.schema();
+        let (mut fields, mut columns): (Vec<_>, Vec<ArrayRef>) = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .filter(|(field, _)| field.name() != &options.fingerprint_column)
+            .map(|(field, column)| (field.clone(), column.clone()))
+            .unzip();
+
+        let (labels, distances) = assignment_columns(&ranked_clusters, options.top_k);
+        fields.push(list_field(LABELS_COLUMN, &labels));
+        fields.push(list_field(DISTANCES_COLUMN, &distances));
+        columns.push(Arc::new(labels));
+        columns.push(Arc::new(distances));
+
+        if options.write_latents {
+            let latents = latent_column(&row_major(&lf_array), lf_array.ncols());
+            fields.push(list_field(LATENT_COLUMN, &latents));
+            columns.push(Arc::new(latents));
+        }
+
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+    }
+}
+
+impl From<ParquetError> for EncoderError {
+    fn from(e: ParquetError) -> Self {
+        EncoderError::Format(format!("Parquet error: {}", e))
+    }
+}
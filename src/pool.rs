@@ -0,0 +1,52 @@
+use crate::config::EncoderConfig;
+use crate::encoder::{get_assets_path, load_model_from_assets, EncoderModel};
+use crate::error::{EncoderError, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `size` independently loaded models, each with its own sessions, handed out round-robin so
+/// concurrent requests spread over separate intra-op thread pools instead of sharing one. A
+/// single `Arc<EncoderModel>` already runs concurrent calls; the pool is for throughput. Every
+/// model holds its own copy of the weights and centroids.
+pub struct EncoderPool {
+    models: Vec<EncoderModel>,
+    next: AtomicUsize,
+}
+
+impl EncoderPool {
+    /// Loads `size` models from the default assets location.
+    pub fn new(size: usize, config: EncoderConfig) -> Result<Self> {
+        EncoderPool::from_path(&get_assets_path()?, size, config)
+    }
+
+    /// Loads `size` models from the assets directory at `path`.
+    pub fn from_path(path: &str, size: usize, config: EncoderConfig) -> Result<Self> {
+        if size == 0 {
+            return Err(EncoderError::InvalidArgument("Encoder pool size must be positive".to_string()));
+        }
+
+        let models = (0..size)
+            .map(|_| load_model_from_assets(path, config.clone()))
+            .collect::<Result<Vec<EncoderModel>>>()?;
+        tracing::info!("Loaded encoder pool of {} models from {}", size, path);
+
+        Ok(EncoderPool {
+            models,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.models.len()
+    }
+
+    /// The next model in round-robin order.
+    pub fn get(&self) -> &EncoderModel {
+        let idx = self.next.fetch_add(1, Ordering::Relaxed) % self.models.len();
+        &self.models[idx]
+    }
+
+    /// `EncoderModel::transform` on the next model.
+    pub fn transform(&self, input_data: &[Vec<i64>]) -> Result<Vec<Vec<i32>>> {
+        self.get().transform(input_data)
+    }
+}
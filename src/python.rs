@@ -0,0 +1,127 @@
+use crate::encoder::{build_encoder_model, build_encoder_model_from_path, EncoderModel};
+use crate::error::EncoderError;
+use ndarray::Array2;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+/// Python wrapper around the Rust model, so notebooks share production's encoding and assignment.
+#[pyclass(name = "EncoderModel", module = "cheminee_similarity_model", frozen)]
+struct PyEncoderModel {
+    model: EncoderModel,
+}
+
+#[pymethods]
+impl PyEncoderModel {
+    /// Loads the model from `assets_path`, or from the default assets location.
+    #[new]
+    #[pyo3(signature = (assets_path=None))]
+    fn new(assets_path: Option<&str>) -> PyResult<Self> {
+        let model = match assets_path {
+            Some(path) => build_encoder_model_from_path(path)?,
+            None => build_encoder_model()?,
+        };
+
+        Ok(PyEncoderModel { model })
+    }
+
+    /// `[rows, latent_dim]` float32 latents for a `[rows, bits]` int64 fingerprint array.
+    fn encode<'py>(&self, py: Python<'py>, fingerprints: PyReadonlyArray2<'py, i64>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let input_data = fingerprint_rows(&fingerprints);
+        let latents = py.allow_threads(|| self.model.encode_latent(&input_data))?;
+
+        Ok(latents.into_pyarray_bound(py))
+    }
+
+    /// `[rows, top_n]` int32 cluster labels, nearest first; ranks a row has no cluster for are -1.
+    #[pyo3(signature = (fingerprints, top_n=1))]
+    fn transform<'py>(
+        &self,
+        py: Python<'py>,
+        fingerprints: PyReadonlyArray2<'py, i64>,
+        top_n: usize,
+    ) -> PyResult<Bound<'py, PyArray2<i32>>> {
+        let (labels, _) = self.assign(py, &fingerprints, top_n)?;
+        Ok(labels.into_pyarray_bound(py))
+    }
+
+    /// Like `transform`, also returning the matching `[rows, top_n]` float32 distances, NaN where
+    /// the label is -1.
+    #[pyo3(signature = (fingerprints, top_n=1))]
+    fn transform_with_distances<'py>(
+        &self,
+        py: Python<'py>,
+        fingerprints: PyReadonlyArray2<'py, i64>,
+        top_n: usize,
+    ) -> PyResult<(Bound<'py, PyArray2<i32>>, Bound<'py, PyArray2<f32>>)> {
+        let (labels, distances) = self.assign(py, &fingerprints, top_n)?;
+        Ok((labels.into_pyarray_bound(py), distances.into_pyarray_bound(py)))
+    }
+
+    /// `[num_clusters]` count of assignments per cluster, when usage tracking is enabled.
+    fn cluster_usage_counts<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u64>> {
+        self.model.cluster_usage_counts().into_pyarray_bound(py)
+    }
+
+    #[getter]
+    fn model_version(&self) -> Option<String> {
+        self.model.model_version().map(str::to_string)
+    }
+
+    #[getter]
+    fn centroid_version(&self) -> Option<String> {
+        self.model.centroid_version().map(str::to_string)
+    }
+}
+
+impl PyEncoderModel {
+    fn assign(
+        &self,
+        py: Python<'_>,
+        fingerprints: &PyReadonlyArray2<'_, i64>,
+        top_n: usize,
+    ) -> PyResult<(Array2<i32>, Array2<f32>)> {
+        if top_n == 0 {
+            return Err(PyValueError::new_err("top_n must be at least 1"));
+        }
+
+        let input_data = fingerprint_rows(fingerprints);
+        let assignments = py.allow_threads(|| self.model.transform_assignments(&input_data, top_n))?;
+
+        let mut labels = Array2::from_elem((assignments.len(), top_n), -1);
+        let mut distances = Array2::from_elem((assignments.len(), top_n), f32::NAN);
+        for (row, assignment) in assignments.into_iter().enumerate() {
+            for (rank, (label, distance)) in assignment.labels.into_iter().zip(assignment.distances).enumerate() {
+                labels[[row, rank]] = label;
+                distances[[row, rank]] = distance;
+            }
+        }
+
+        Ok((labels, distances))
+    }
+}
+
+fn fingerprint_rows(fingerprints: &PyReadonlyArray2<'_, i64>) -> Vec<Vec<i64>> {
+    fingerprints
+        .as_array()
+        .outer_iter()
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+impl From<EncoderError> for PyErr {
+    fn from(e: EncoderError) -> Self {
+        match e {
+            EncoderError::ShapeMismatch(_) | EncoderError::InvalidArgument(_) | EncoderError::NanLatent { .. } => {
+                PyValueError::new_err(e.to_string())
+            }
+            _ => PyRuntimeError::new_err(e.to_string()),
+        }
+    }
+}
+
+#[pymodule]
+fn cheminee_similarity_model(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEncoderModel>()?;
+    Ok(())
+}
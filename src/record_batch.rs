@@ -0,0 +1,157 @@
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use arrow::array::{
+    Array, ArrayRef, AsArray, FixedSizeListArray, FixedSizeListBuilder, Float32Array, Float32Builder, Int32Array,
+    Int32Builder,
+};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Int64Type, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+pub const LABEL_COLUMN: &str = "cluster_label";
+pub const DISTANCE_COLUMN: &str = "cluster_distance";
+#[cfg(feature = "parquet")]
+pub(crate) const LABELS_COLUMN: &str = "cluster_labels";
+#[cfg(feature = "parquet")]
+pub(crate) const DISTANCES_COLUMN: &str = "cluster_distances";
+#[cfg(feature = "parquet")]
+pub(crate) const LATENT_COLUMN: &str = "latent";
+
+/// Reads fingerprints from a list or fixed-size-list column of integer or boolean bits.
+pub(crate) fn fingerprint_rows(batch: &RecordBatch, column_name: &str) -> Result<Vec<Vec<i64>>> {
+    let column = batch
+        .column_by_name(column_name)
+        .ok_or(EncoderError::InvalidArgument(format!("No fingerprint column named {}", column_name)))?;
+
+    let rows = match column.data_type() {
+        DataType::FixedSizeList(..) => column.as_fixed_size_list().iter().collect::<Vec<Option<ArrayRef>>>(),
+        DataType::List(_) => column.as_list::<i32>().iter().collect(),
+        DataType::LargeList(_) => column.as_list::<i64>().iter().collect(),
+        other => {
+            return Err(EncoderError::InvalidArgument(format!(
+                "Fingerprint column {} has type {}, expected a list of bits",
+                column_name, other
+            )))
+        }
+    };
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(row_idx, bits)| {
+            let bits = bits.ok_or_else(|| {
+                EncoderError::ShapeMismatch(format!("Fingerprint column {} is null in row {}", column_name, row_idx))
+            })?;
+            // Casting would read null bits as 0
+            if bits.null_count() > 0 {
+                return Err(EncoderError::ShapeMismatch(format!(
+                    "Fingerprint column {} has {} null bits in row {}",
+                    column_name,
+                    bits.null_count(),
+                    row_idx
+                )));
+            }
+            let bits = cast(&bits, &DataType::Int64)?;
+            Ok(bits.as_primitive::<Int64Type>().values().to_vec())
+        })
+        .collect()
+}
+
+#[cfg(feature = "parquet")]
+/// `[rows, k]` label and distance columns; rows ranked with fewer than `k` clusters, e.g. ones
+/// skipped by the NaN policy, are null.
+pub(crate) fn assignment_columns(ranked_clusters: &[Vec<(i32, f32)>], k: usize) -> (FixedSizeListArray, FixedSizeListArray) {
+    let mut labels = FixedSizeListBuilder::new(Int32Builder::new(), k as i32);
+    let mut distances = FixedSizeListBuilder::new(Float32Builder::new(), k as i32);
+
+    for row in ranked_clusters {
+        let valid = row.len() >= k;
+        for idx in 0..k {
+            let entry = row.get(idx).filter(|_| valid);
+            labels.values().append_option(entry.map(|(label, _)| *label));
+            distances.values().append_option(entry.map(|(_, distance)| *distance));
+        }
+        labels.append(valid);
+        distances.append(valid);
+    }
+
+    (labels.finish(), distances.finish())
+}
+
+#[cfg(feature = "parquet")]
+/// `[rows, dim]` latents as a fixed-size-list column.
+pub(crate) fn latent_column(latents: &[f32], dim: usize) -> FixedSizeListArray {
+    let mut column = FixedSizeListBuilder::new(Float32Builder::with_capacity(latents.len()), dim as i32);
+    for row in latents.chunks(dim) {
+        column.values().append_slice(row);
+        column.append(true);
+    }
+
+    column.finish()
+}
+
+#[cfg(feature = "parquet")]
+pub(crate) fn list_field(name: &str, column: &FixedSizeListArray) -> Arc<Field> {
+    Arc::new(Field::new(name, column.data_type().clone(), true))
+}
+
+impl From<ArrowError> for EncoderError {
+    fn from(e: ArrowError) -> Self {
+        EncoderError::Format(format!("Arrow error: {}", e))
+    }
+}
+
+impl EncoderModel {
+    /// Appends `cluster_label` and `cluster_distance` columns with each row's nearest cluster to
+    /// `batch`. Fingerprints are read from the batch's only list or fixed-size-list column, of
+    /// integer or boolean bits; rows the NaN policy skips get nulls.
+    pub fn transform_arrow(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let schema = batch.schema();
+        let mut fingerprint_columns = schema
+            .fields()
+            .iter()
+            .filter(|field| {
+                matches!(
+                    field.data_type(),
+                    DataType::FixedSizeList(..) | DataType::List(_) | DataType::LargeList(_)
+                )
+            });
+
+        let fingerprint_column = match (fingerprint_columns.next(), fingerprint_columns.next()) {
+            (Some(field), None) => field.name().clone(),
+            (None, _) => {
+                return Err(EncoderError::InvalidArgument(
+                    "Batch has no list fingerprint column".to_string(),
+                ))
+            }
+            (Some(_), Some(_)) => {
+                return Err(EncoderError::InvalidArgument(
+                    "Batch has more than one list column".to_string(),
+                ))
+            }
+        };
+
+        let fingerprints = fingerprint_rows(batch, &fingerprint_column)?;
+        let ranked_clusters = self.rank_top_clusters(&fingerprints, Some(1))?;
+
+        let labels = ranked_clusters
+            .iter()
+            .map(|row| row.first().map(|(label, _)| *label))
+            .collect::<Int32Array>();
+        let distances = ranked_clusters
+            .iter()
+            .map(|row| row.first().map(|(_, distance)| *distance))
+            .collect::<Float32Array>();
+
+        let mut fields = schema.fields().iter().cloned().collect::<Vec<Arc<Field>>>();
+        fields.push(Arc::new(Field::new(LABEL_COLUMN, DataType::Int32, true)));
+        fields.push(Arc::new(Field::new(DISTANCE_COLUMN, DataType::Float32, true)));
+
+        let mut columns = batch.columns().to_vec();
+        columns.push(Arc::new(labels));
+        columns.push(Arc::new(distances));
+
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+    }
+}
@@ -0,0 +1,40 @@
+use crate::encoder::{load_model_from_assets, EncoderModel};
+use crate::error::Result;
+use std::sync::{Arc, RwLock};
+
+/// A swappable handle to the current `EncoderModel` for long-running services. Callers take a
+/// snapshot with `current` per request; a reload builds the new model off to the side and swaps
+/// it in, so in-flight requests finish on the model they started with and the old one is freed
+/// once the last of them drops its snapshot.
+pub struct ModelRegistry {
+    current: RwLock<Arc<EncoderModel>>,
+}
+
+impl ModelRegistry {
+    pub fn new(model: EncoderModel) -> Self {
+        ModelRegistry {
+            current: RwLock::new(Arc::new(model)),
+        }
+    }
+
+    pub fn current(&self) -> Arc<EncoderModel> {
+        // The lock only guards a pointer swap, so a poisoned lock still holds a complete model
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Installs `model` and returns the one it replaced.
+    pub fn swap(&self, model: EncoderModel) -> Arc<EncoderModel> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *current, Arc::new(model))
+    }
+
+    /// Loads the encoder and centroids from the assets directory at `path` with the current
+    /// model's config and swaps them in. On error the current model stays in place.
+    pub fn reload(&self, path: &str) -> Result<Arc<EncoderModel>> {
+        let config = self.current().config().clone();
+        let model = load_model_from_assets(path, config)?;
+        tracing::info!("Reloaded encoder model from {}", path);
+
+        Ok(self.swap(model))
+    }
+}
@@ -0,0 +1,231 @@
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use crate::stats::ClusterAssignment;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::time::{timeout_at, Instant};
+
+const JOB_QUEUE_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// Rows gathered from concurrent requests before a batch runs
+    pub max_batch_size: usize,
+    /// How long the first request of a batch waits for others to join it
+    pub max_batch_delay: Duration,
+    /// Batches run at once per endpoint; the next batch starts gathering while earlier ones run
+    pub max_concurrent_batches: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            max_batch_size: 256,
+            max_batch_delay: Duration::from_millis(5),
+            max_concurrent_batches: 4,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EncodeRequest {
+    pub fingerprints: Vec<Vec<i64>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EncodeResponse {
+    pub latents: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignRequest {
+    pub fingerprints: Vec<Vec<i64>>,
+    /// Clusters returned per row, 1 when omitted
+    pub top_n: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssignResponse {
+    pub assignments: Vec<ClusterAssignment>,
+}
+
+struct Job<T> {
+    rows: Vec<Vec<i64>>,
+    top_n: usize,
+    reply: oneshot::Sender<Result<Vec<T>>>,
+    // The submitting request's span, linked from the batch span that serves it
+    span: tracing::Span,
+}
+
+type RunBatch<T> = fn(&EncoderModel, &[Vec<i64>], usize) -> Result<Vec<T>>;
+
+#[derive(Clone)]
+struct AppState {
+    encode_jobs: mpsc::Sender<Job<Vec<f32>>>,
+    assign_jobs: mpsc::Sender<Job<ClusterAssignment>>,
+}
+
+/// Routes `POST /encode` and `POST /assign`. Rows from concurrent requests are merged into shared
+/// model runs; must be called inside a Tokio runtime, which hosts the batching tasks.
+pub fn router(model: Arc<EncoderModel>, config: ServerConfig) -> Router {
+    let state = AppState {
+        encode_jobs: spawn_batcher(model.clone(), config.clone(), encode_rows),
+        assign_jobs: spawn_batcher(model, config, assign_rows),
+    };
+
+    Router::new()
+        .route("/encode", post(encode))
+        .route("/assign", post(assign))
+        .with_state(state)
+}
+
+pub async fn serve(model: Arc<EncoderModel>, addr: SocketAddr, config: ServerConfig) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Serving encoder model on {}", listener.local_addr()?);
+
+    axum::serve(listener, router(model, config)).await?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "encode_request", skip_all, fields(rows = request.fingerprints.len()))]
+async fn encode(State(state): State<AppState>, Json(request): Json<EncodeRequest>) -> Result<Json<EncodeResponse>> {
+    let latents = submit(&state.encode_jobs, request.fingerprints, 0).await?;
+    Ok(Json(EncodeResponse { latents }))
+}
+
+#[tracing::instrument(name = "assign_request", skip_all, fields(rows = request.fingerprints.len(), top_n = ?request.top_n))]
+async fn assign(State(state): State<AppState>, Json(request): Json<AssignRequest>) -> Result<Json<AssignResponse>> {
+    let top_n = request.top_n.unwrap_or(1);
+    if top_n == 0 {
+        return Err(EncoderError::InvalidArgument("top_n must be at least 1".to_string()));
+    }
+
+    // Batches rank up to the largest `top_n` among their requests
+    let mut assignments = submit(&state.assign_jobs, request.fingerprints, top_n).await?;
+    for assignment in &mut assignments {
+        assignment.labels.truncate(top_n);
+        assignment.distances.truncate(top_n);
+    }
+
+    Ok(Json(AssignResponse { assignments }))
+}
+
+async fn submit<T>(jobs: &mpsc::Sender<Job<T>>, rows: Vec<Vec<i64>>, top_n: usize) -> Result<Vec<T>> {
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (reply, response) = oneshot::channel();
+    let span = tracing::Span::current();
+    jobs.send(Job { rows, top_n, reply, span })
+        .await
+        .map_err(|_| EncoderError::Model("Batching task has stopped".to_string()))?;
+
+    response
+        .await
+        .map_err(|_| EncoderError::Model("Batch was dropped before completing".to_string()))?
+}
+
+fn spawn_batcher<T: Send + 'static>(
+    model: Arc<EncoderModel>,
+    config: ServerConfig,
+    run_batch: RunBatch<T>,
+) -> mpsc::Sender<Job<T>> {
+    let (sender, mut receiver) = mpsc::channel::<Job<T>>(JOB_QUEUE_CAPACITY);
+    let running_batches = Arc::new(Semaphore::new(config.max_concurrent_batches.max(1)));
+
+    tokio::spawn(async move {
+        while let Some(first_job) = receiver.recv().await {
+            let deadline = Instant::now() + config.max_batch_delay;
+            let mut batch_rows = first_job.rows.len();
+            let mut jobs = vec![first_job];
+
+            while batch_rows < config.max_batch_size {
+                match timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(job)) => {
+                        batch_rows += job.rows.len();
+                        jobs.push(job);
+                    }
+                    _ => break,
+                }
+            }
+
+            // Waits only when `max_concurrent_batches` are already running; the permit is released
+            // when the blocking task finishes
+            let Ok(permit) = running_batches.clone().acquire_owned().await else {
+                break;
+            };
+            let model = model.clone();
+            tokio::task::spawn_blocking(move || {
+                run_jobs(&model, jobs, run_batch);
+                drop(permit);
+            });
+        }
+    });
+
+    sender
+}
+
+fn run_jobs<T>(model: &EncoderModel, jobs: Vec<Job<T>>, run_batch: RunBatch<T>) {
+    let top_n = jobs.iter().map(|job| job.top_n).max().unwrap_or(0);
+    let rows = jobs.iter().flat_map(|job| job.rows.iter().cloned()).collect::<Vec<Vec<i64>>>();
+
+    let span = tracing::info_span!("batch", jobs = jobs.len(), rows = rows.len(), top_n);
+    for job in &jobs {
+        span.follows_from(&job.span);
+    }
+    let _entered = span.enter();
+
+    match run_batch(model, &rows, top_n) {
+        Ok(results) => {
+            let mut results = results.into_iter();
+            for job in jobs {
+                let job_results = results.by_ref().take(job.rows.len()).collect();
+                let _ = job.reply.send(Ok(job_results));
+            }
+        }
+        // One malformed request fails the merged batch; rerun each alone so only it sees the error
+        Err(e) if jobs.len() > 1 => {
+            tracing::warn!(error = %e, jobs = jobs.len(), "Merged batch failed, rerunning each request alone");
+            for job in jobs {
+                let _entered = job.span.enter();
+                let _ = job.reply.send(run_batch(model, &job.rows, job.top_n));
+            }
+        }
+        Err(e) => {
+            if let Some(job) = jobs.into_iter().next() {
+                let _ = job.reply.send(Err(e));
+            }
+        }
+    }
+}
+
+fn encode_rows(model: &EncoderModel, rows: &[Vec<i64>], _top_n: usize) -> Result<Vec<Vec<f32>>> {
+    let latents = model.encode_latent(rows)?;
+    Ok(latents.outer_iter().map(|latent| latent.to_vec()).collect())
+}
+
+fn assign_rows(model: &EncoderModel, rows: &[Vec<i64>], top_n: usize) -> Result<Vec<ClusterAssignment>> {
+    model.transform_assignments(rows, top_n)
+}
+
+impl IntoResponse for EncoderError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            EncoderError::ShapeMismatch(_) | EncoderError::InvalidArgument(_) | EncoderError::NanLatent { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status, self.to_string()).into_response()
+    }
+}
@@ -0,0 +1,132 @@
+use crate::config::{EncoderConfig, SessionConfig};
+use crate::error::{EncoderError, Result};
+use tensorflow::SessionOptions;
+
+// Field numbers from tensorflow/core/protobuf/{config,rewriter_config}.proto
+const CONFIG_DEVICE_COUNT: u32 = 1;
+const CONFIG_INTRA_OP_PARALLELISM_THREADS: u32 = 2;
+const CONFIG_INTER_OP_PARALLELISM_THREADS: u32 = 5;
+const CONFIG_GPU_OPTIONS: u32 = 6;
+const CONFIG_ALLOW_SOFT_PLACEMENT: u32 = 7;
+const CONFIG_LOG_DEVICE_PLACEMENT: u32 = 8;
+const CONFIG_GRAPH_OPTIONS: u32 = 10;
+const GPU_PER_PROCESS_GPU_MEMORY_FRACTION: u32 = 1;
+const GPU_ALLOW_GROWTH: u32 = 4;
+const GPU_VISIBLE_DEVICE_LIST: u32 = 5;
+const MAP_ENTRY_KEY: u32 = 1;
+const MAP_ENTRY_VALUE: u32 = 2;
+const GRAPH_REWRITE_OPTIONS: u32 = 10;
+const REWRITE_AUTO_MIXED_PRECISION_ONEDNN_BFLOAT16: u32 = 31;
+const TOGGLE_ON: u64 = 1;
+
+/// Options for the encoder session: `config.session` plus the bfloat16 rewrite.
+pub fn session_options(config: &EncoderConfig) -> Result<SessionOptions> {
+    options_from_proto(config_proto(&config.session, config.bfloat16)?)
+}
+
+/// Options for the assignment, distance and decoder sessions, which never run in reduced precision.
+pub fn assignment_session_options(session: &SessionConfig) -> Result<SessionOptions> {
+    options_from_proto(config_proto(session, false)?)
+}
+
+fn options_from_proto(config_proto: Vec<u8>) -> Result<SessionOptions> {
+    let mut session_options = SessionOptions::new();
+
+    if !config_proto.is_empty() {
+        session_options.set_config(&config_proto)?;
+    }
+
+    Ok(session_options)
+}
+
+/// The serialized `ConfigProto` behind the session options, empty when nothing is set.
+pub fn config_proto(session: &SessionConfig, bfloat16: bool) -> Result<Vec<u8>> {
+    if let Some(fraction) = session.gpu_memory_fraction {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(EncoderError::InvalidArgument(format!(
+                "GPU memory fraction must be in (0, 1], got {}",
+                fraction
+            )));
+        }
+    }
+
+    let mut config_proto = Vec::new();
+
+    if session.disable_gpu {
+        let mut device_count = Vec::new();
+        encode_bytes_field(MAP_ENTRY_KEY, b"GPU", &mut device_count);
+        encode_varint_field(MAP_ENTRY_VALUE, 0, &mut device_count);
+        encode_bytes_field(CONFIG_DEVICE_COUNT, &device_count, &mut config_proto);
+    }
+    if let Some(threads) = session.intra_op_threads {
+        encode_varint_field(CONFIG_INTRA_OP_PARALLELISM_THREADS, threads as u64, &mut config_proto);
+    }
+    if let Some(threads) = session.inter_op_threads {
+        encode_varint_field(CONFIG_INTER_OP_PARALLELISM_THREADS, threads as u64, &mut config_proto);
+    }
+
+    let mut gpu_options = Vec::new();
+    if let Some(fraction) = session.gpu_memory_fraction {
+        encode_fixed64_field(GPU_PER_PROCESS_GPU_MEMORY_FRACTION, fraction.to_bits(), &mut gpu_options);
+    }
+    if session.allow_growth {
+        encode_varint_field(GPU_ALLOW_GROWTH, 1, &mut gpu_options);
+    }
+    if let Some(visible_devices) = &session.visible_devices {
+        encode_bytes_field(GPU_VISIBLE_DEVICE_LIST, visible_devices.as_bytes(), &mut gpu_options);
+    }
+    if !gpu_options.is_empty() {
+        encode_bytes_field(CONFIG_GPU_OPTIONS, &gpu_options, &mut config_proto);
+    }
+
+    if session.allow_soft_placement {
+        encode_varint_field(CONFIG_ALLOW_SOFT_PLACEMENT, 1, &mut config_proto);
+    }
+    if session.log_device_placement {
+        encode_varint_field(CONFIG_LOG_DEVICE_PLACEMENT, 1, &mut config_proto);
+    }
+
+    let mut rewrite_options = Vec::new();
+    if bfloat16 {
+        encode_varint_field(
+            REWRITE_AUTO_MIXED_PRECISION_ONEDNN_BFLOAT16,
+            TOGGLE_ON,
+            &mut rewrite_options,
+        );
+    }
+
+    let mut graph_options = Vec::new();
+    if !rewrite_options.is_empty() {
+        encode_bytes_field(GRAPH_REWRITE_OPTIONS, &rewrite_options, &mut graph_options);
+    }
+
+    if !graph_options.is_empty() {
+        encode_bytes_field(CONFIG_GRAPH_OPTIONS, &graph_options, &mut config_proto);
+    }
+
+    Ok(config_proto)
+}
+
+fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn encode_varint_field(field: u32, value: u64, buf: &mut Vec<u8>) {
+    encode_varint((field as u64) << 3, buf);
+    encode_varint(value, buf);
+}
+
+fn encode_fixed64_field(field: u32, value: u64, buf: &mut Vec<u8>) {
+    encode_varint(((field as u64) << 3) | 1, buf);
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn encode_bytes_field(field: u32, bytes: &[u8], buf: &mut Vec<u8>) {
+    encode_varint(((field as u64) << 3) | 2, buf);
+    encode_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
@@ -0,0 +1,53 @@
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use crate::fingerprint::FingerprintKind;
+use rdkit::ROMol;
+
+/// Width of the RDKit fingerprint the bundled encoder was trained on
+pub const FINGERPRINT_BITS: usize = 2048;
+
+/// Parses `smiles` and computes the fingerprint the encoder expects, as one `0`/`1` per bit.
+pub fn smiles_fingerprint(smiles: &str) -> Result<Vec<i64>> {
+    let mol = ROMol::from_smiles(smiles)
+        .map_err(|e| EncoderError::InvalidArgument(format!("Failed to parse SMILES {:?}: {}", smiles, e)))?;
+
+    let fingerprint = mol.fingerprint();
+    if fingerprint.0.len() != FINGERPRINT_BITS {
+        return Err(EncoderError::ShapeMismatch(format!(
+            "RDKit produced a {}-bit fingerprint, but the encoder expects {} bits",
+            fingerprint.0.len(),
+            FINGERPRINT_BITS
+        )));
+    }
+
+    Ok(fingerprint.0.iter().map(|bit| *bit as i64).collect())
+}
+
+impl EncoderModel {
+    /// Like `transform`, but computes each row's fingerprint from a SMILES string. Fails on the
+    /// first unparsable SMILES, naming its row, and on models whose manifest declares a
+    /// fingerprint other than RDKit's.
+    pub fn transform_smiles(&self, smiles: &[&str]) -> Result<Vec<Vec<i32>>> {
+        if let Some(spec) = self.fingerprint_spec().filter(|spec| spec.kind != FingerprintKind::Rdkit) {
+            return Err(EncoderError::Model(format!(
+                "The encoder expects {:?} fingerprints, but transform_smiles computes RDKit fingerprints",
+                spec.kind
+            )));
+        }
+
+        let fingerprints = smiles
+            .iter()
+            .enumerate()
+            .map(|(row, smiles)| {
+                smiles_fingerprint(smiles).map_err(|e| match e {
+                    EncoderError::InvalidArgument(message) => {
+                        EncoderError::InvalidArgument(format!("Row {}: {}", row, message))
+                    }
+                    e => e,
+                })
+            })
+            .collect::<Result<Vec<Vec<i64>>>>()?;
+
+        self.transform(&fingerprints)
+    }
+}
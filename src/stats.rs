@@ -0,0 +1,165 @@
+use crate::error::{EncoderError, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DistanceSummary {
+    pub mean: f32,
+    pub max: f32,
+    pub p95: f32,
+}
+
+/// A query's nearest cluster with its distance placed relative to the rest of its batch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchRelativeAssignment {
+    pub cluster_id: i32,
+    pub distance: f32,
+    /// Fraction of assigned rows in the batch whose nearest distance is at most this one
+    pub distance_percentile: f32,
+}
+
+/// A query's ranked clusters, nearest first, with `distances[i]` belonging to `labels[i]`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClusterAssignment {
+    pub labels: Vec<i32>,
+    pub distances: Vec<f32>,
+}
+
+impl From<Vec<(i32, f32)>> for ClusterAssignment {
+    fn from(ranked_clusters: Vec<(i32, f32)>) -> Self {
+        let (labels, distances) = ranked_clusters.into_iter().unzip();
+        ClusterAssignment { labels, distances }
+    }
+}
+
+/// Summarizes per-query nearest distances, ignoring `NaN` entries from unassigned rows.
+pub fn summarize_distances(distances: &[f32]) -> Result<DistanceSummary> {
+    let mut sorted_distances = distances
+        .iter()
+        .copied()
+        .filter(|distance| !distance.is_nan())
+        .collect::<Vec<f32>>();
+
+    if sorted_distances.is_empty() {
+        return Err(EncoderError::ShapeMismatch("No assigned rows to summarize".to_string()));
+    }
+
+    sorted_distances.sort_by(f32::total_cmp);
+
+    let mean = sorted_distances.iter().sum::<f32>() / sorted_distances.len() as f32;
+    let max = sorted_distances[sorted_distances.len() - 1];
+    let p95 = percentile(&sorted_distances, 0.95);
+
+    Ok(DistanceSummary { mean, max, p95 })
+}
+
+// Linear interpolation between closest ranks; `sorted_values` must be non-empty and ascending
+pub fn percentile(sorted_values: &[f32], quantile: f64) -> f32 {
+    let position = quantile.clamp(0.0, 1.0) * (sorted_values.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = (position - lower as f64) as f32;
+
+    sorted_values[lower] + (sorted_values[upper] - sorted_values[lower]) * fraction
+}
+
+/// Shannon entropy of `counts` divided by `ln(max_categories)`, so `1.0` means the counts are
+/// spread evenly over `max_categories` bins and `0.0` means they all fall in one.
+pub fn normalized_entropy(counts: &[u64], max_categories: usize) -> f32 {
+    let total = counts.iter().sum::<u64>();
+    if total == 0 || max_categories < 2 {
+        return 0.0;
+    }
+
+    let entropy = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.ln()
+        })
+        .sum::<f64>();
+
+    (entropy / (max_categories as f64).ln()) as f32
+}
+
+/// Smallest threshold such that at least `target_recall` of `distances` are at or below it.
+/// `NaN` entries stand for misses that no threshold can recover.
+pub fn recall_threshold(distances: &[f32], target_recall: f64) -> Result<f32> {
+    if !(target_recall > 0.0 && target_recall <= 1.0) {
+        return Err(EncoderError::InvalidArgument(format!("Target recall must be in (0, 1], got {}", target_recall)));
+    }
+
+    if distances.is_empty() {
+        return Err(EncoderError::ShapeMismatch("No distances to calibrate against".to_string()));
+    }
+
+    let mut sorted_distances = distances
+        .iter()
+        .copied()
+        .filter(|distance| !distance.is_nan())
+        .collect::<Vec<f32>>();
+    sorted_distances.sort_by(f32::total_cmp);
+
+    let required = (target_recall * distances.len() as f64).ceil() as usize;
+    if required > sorted_distances.len() {
+        return Err(EncoderError::InvalidArgument(format!(
+            "Target recall {} is unreachable: only {} of {} rows can be recovered",
+            target_recall,
+            sorted_distances.len(),
+            distances.len()
+        )));
+    }
+
+    Ok(sorted_distances[required.max(1) - 1])
+}
+
+/// `softmax(-distance / temperature)`, shifted by the smallest distance for numerical stability.
+pub fn softmax_over_distances(distances: &[f32], temperature: f32) -> Vec<f64> {
+    let min_distance = distances.iter().copied().fold(f32::INFINITY, f32::min) as f64;
+    let temperature = temperature as f64;
+
+    let scores = distances
+        .iter()
+        .map(|&distance| (-(distance as f64 - min_distance) / temperature).exp())
+        .collect::<Vec<f64>>();
+
+    let total = scores.iter().sum::<f64>();
+    scores.into_iter().map(|score| score / total).collect()
+}
+
+/// Jensen-Shannon divergence of two distributions over the same outcomes, in nats, so it lies in
+/// `[0, ln 2]`.
+pub fn jensen_shannon_divergence(p: &[f64], q: &[f64]) -> f32 {
+    let kl_to_mixture = |x: &[f64], y: &[f64]| {
+        x.iter()
+            .zip(y)
+            .filter(|(xi, _)| **xi > 0.0)
+            .map(|(&xi, &yi)| xi * (2.0 * xi / (xi + yi)).ln())
+            .sum::<f64>()
+    };
+
+    (0.5 * kl_to_mixture(p, q) + 0.5 * kl_to_mixture(q, p)) as f32
+}
+
+/// For each value, the fraction of non-`NaN` values in the slice that are less than or equal to
+/// it. `NaN` entries map to `NaN`.
+pub fn percentile_ranks(values: &[f32]) -> Vec<f32> {
+    let mut sorted_values = values
+        .iter()
+        .copied()
+        .filter(|value| !value.is_nan())
+        .collect::<Vec<f32>>();
+    sorted_values.sort_by(f32::total_cmp);
+
+    values
+        .iter()
+        .map(|value| {
+            if value.is_nan() {
+                return f32::NAN;
+            }
+
+            let at_or_below = sorted_values.partition_point(|sorted| sorted <= value);
+            at_or_below as f32 / sorted_values.len() as f32
+        })
+        .collect()
+}
@@ -0,0 +1,37 @@
+use crate::encoder::EncoderModel;
+use crate::error::{EncoderError, Result};
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+
+impl EncoderModel {
+    /// Micro-batches fingerprints from `input` and yields one ranked-label row per fingerprint.
+    ///
+    /// Each batch runs on tokio's blocking thread pool, like `transform_async`, so polling the
+    /// stream never stalls a runtime worker; batches still run one at a time, in order.
+    pub fn transform_stream<S>(self: Arc<Self>, input: S, batch_size: usize) -> impl Stream<Item = Result<Vec<i32>>>
+    where
+        S: Stream<Item = Vec<i64>>,
+    {
+        input
+            .chunks(batch_size.max(1))
+            .then(move |batch| {
+                let model = Arc::clone(&self);
+                async move {
+                    let total = batch.len();
+                    match tokio::task::spawn_blocking(move || model.transform(&batch)).await {
+                        Ok(result) => result,
+                        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                        // Only happens when the runtime shuts down before the task starts
+                        Err(_) => Err(EncoderError::Cancelled { assigned: 0, total }),
+                    }
+                }
+            })
+            .flat_map(|result| {
+                let rows = match result {
+                    Ok(rows) => rows.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(rows)
+            })
+    }
+}
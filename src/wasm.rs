@@ -0,0 +1,94 @@
+use crate::csv_rows::parse_float_rows;
+use crate::error::EncoderError;
+use crate::metric::DistanceMetric;
+use wasm_bindgen::prelude::*;
+
+/// Label written for ranks a row has no cluster for
+const NO_LABEL: i32 = -1;
+
+/// Assigns precomputed latents to centroids from JavaScript. Latents and centroids cross the
+/// boundary as flat row-major `Float32Array`s; results come back flat as `[rows, top_n]`.
+#[wasm_bindgen]
+pub struct CentroidAssigner {
+    centroids: Vec<f32>,
+    dim: usize,
+}
+
+#[wasm_bindgen]
+impl CentroidAssigner {
+    #[wasm_bindgen(constructor)]
+    pub fn new(centroids: Vec<f32>, dim: usize) -> Result<CentroidAssigner, JsError> {
+        if dim == 0 || centroids.is_empty() || centroids.len() % dim != 0 {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "{} centroid values do not form rows of width {}",
+                centroids.len(),
+                dim
+            ))
+            .into());
+        }
+
+        Ok(CentroidAssigner { centroids, dim })
+    }
+
+    /// Parses a headerless CSV with one centroid per line, as shipped with the model assets.
+    #[wasm_bindgen(js_name = fromCsv)]
+    pub fn from_csv(csv: &str) -> Result<CentroidAssigner, JsError> {
+        let (centroids, dim) = parse_float_rows(csv)?;
+        CentroidAssigner::new(centroids, dim)
+    }
+
+    #[wasm_bindgen(getter, js_name = numClusters)]
+    pub fn num_clusters(&self) -> usize {
+        self.centroids.len() / self.dim
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Nearest `top_n` cluster labels per latent row, nearest first.
+    pub fn assign(&self, latents: &[f32], top_n: usize) -> Result<Vec<i32>, JsError> {
+        let ranked_clusters = self.rank(latents, top_n)?;
+
+        let mut labels = vec![NO_LABEL; ranked_clusters.len() * top_n];
+        for (row_labels, ranked) in labels.chunks_mut(top_n).zip(ranked_clusters) {
+            for (label, (cluster_id, _)) in row_labels.iter_mut().zip(ranked) {
+                *label = cluster_id;
+            }
+        }
+
+        Ok(labels)
+    }
+
+    /// Distances matching `assign`'s labels, `NaN` where a row has no cluster for the rank.
+    pub fn distances(&self, latents: &[f32], top_n: usize) -> Result<Vec<f32>, JsError> {
+        let ranked_clusters = self.rank(latents, top_n)?;
+
+        let mut distances = vec![f32::NAN; ranked_clusters.len() * top_n];
+        for (row_distances, ranked) in distances.chunks_mut(top_n).zip(ranked_clusters) {
+            for (distance, (_, cluster_distance)) in row_distances.iter_mut().zip(ranked) {
+                *distance = cluster_distance;
+            }
+        }
+
+        Ok(distances)
+    }
+}
+
+impl CentroidAssigner {
+    fn rank(&self, latents: &[f32], top_n: usize) -> Result<Vec<Vec<(i32, f32)>>, EncoderError> {
+        if top_n == 0 {
+            return Err(EncoderError::InvalidArgument("top_n must be at least 1".to_string()));
+        }
+        if latents.len() % self.dim != 0 {
+            return Err(EncoderError::ShapeMismatch(format!(
+                "{} latent values do not form rows of width {}",
+                latents.len(),
+                self.dim
+            )));
+        }
+
+        Ok(DistanceMetric::default().rank(latents, &self.centroids, self.dim, Some(top_n)))
+    }
+}
@@ -0,0 +1,31 @@
+use cheminee_similarity_model::ann::{top_k_recall, CentroidIndex, HnswConfig};
+use cheminee_similarity_model::centroids::{load_centroids_from_rows, Centroids};
+use cheminee_similarity_model::metric::DistanceMetric;
+
+#[test]
+fn test_top_k_recall() {
+    let approximate = vec![vec![(0, 0.1), (1, 0.2)], vec![(2, 0.1), (5, 0.3)], vec![]];
+    let exact = vec![vec![(1, 0.2), (0, 0.1)], vec![(2, 0.1), (3, 0.2)], vec![(4, 0.0), (6, 0.1)]];
+
+    // The empty third row was skipped by the approximate search and is not counted
+    assert_eq!(top_k_recall(&approximate, &exact), 0.75);
+    assert_eq!(top_k_recall(&[], &[]), 1.0);
+}
+
+#[test]
+fn test_centroid_index_matches_exact_on_small_sets() {
+    let rows = (0..32).map(|i| vec![i as f32, (i % 4) as f32]);
+    let centroids = Centroids {
+        coordinates: load_centroids_from_rows(rows).unwrap(),
+        radii: None,
+    };
+    let index = CentroidIndex::build(&centroids, &HnswConfig::default(), DistanceMetric::Euclidean);
+
+    let latents = [10.2f32, 2.0, 25.0, 1.0];
+    let exact = DistanceMetric::Euclidean.rank(&latents, centroids.coordinates.as_slice().unwrap(), 2, Some(3));
+    let approximate = latents.chunks(2).map(|latent| index.search(latent, 3)).collect::<Vec<_>>();
+
+    assert_eq!(top_k_recall(&approximate, &exact), 1.0);
+    assert_eq!(approximate[0][0].0, 10);
+    assert!((approximate[0][0].1 - exact[0][0].1).abs() < 1e-5);
+}
@@ -0,0 +1,51 @@
+use cheminee_similarity_model::encoder::{build_encoder_model, build_encoder_model_from_archive, get_assets_path};
+use cheminee_similarity_model::error::EncoderError;
+use std::fs::File;
+use std::io::Write;
+
+#[test]
+fn test_archive_with_nested_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("similarity.tar");
+
+    let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+    builder
+        .append_dir_all("similarity-0.1.0", get_assets_path().unwrap())
+        .unwrap();
+    builder.finish().unwrap();
+
+    let archived_model = build_encoder_model_from_archive(archive_path.to_str().unwrap()).unwrap();
+    let encoder_model = build_encoder_model().unwrap();
+
+    let input_data = vec![vec![0; 2048], vec![1; 2048]];
+    assert_eq!(
+        archived_model.transform(&input_data).unwrap(),
+        encoder_model.transform(&input_data).unwrap()
+    );
+}
+
+#[test]
+fn test_archive_without_encoder_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("similarity.zip");
+
+    let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+    writer
+        .start_file("similarity-0.1.0/README.txt", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(b"no encoder here").unwrap();
+    writer.finish().unwrap();
+
+    let result = build_encoder_model_from_archive(archive_path.to_str().unwrap());
+    assert!(matches!(result, Err(EncoderError::MissingAssets(_))));
+}
+
+#[test]
+fn test_unsupported_archive_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive_path = dir.path().join("similarity.rar");
+    File::create(&archive_path).unwrap();
+
+    let result = build_encoder_model_from_archive(archive_path.to_str().unwrap());
+    assert!(matches!(result, Err(EncoderError::InvalidArgument(_))));
+}
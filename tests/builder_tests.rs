@@ -0,0 +1,38 @@
+use cheminee_similarity_model::builder::EncoderModelBuilder;
+use cheminee_similarity_model::encoder::get_assets_path;
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::metric::DistanceMetric;
+
+#[test]
+fn test_builder_applies_settings() {
+    let encoder_model = EncoderModelBuilder::new()
+        .assets_path(get_assets_path().unwrap())
+        .metric(DistanceMetric::Cosine)
+        .intra_op_threads(2)
+        .top_n(3)
+        .build()
+        .unwrap();
+
+    assert_eq!(encoder_model.config().metric, DistanceMetric::Cosine);
+    assert_eq!(encoder_model.config().session.intra_op_threads, Some(2));
+
+    let ranked_cluster_labels = encoder_model.transform(&[vec![0; 2048]]).unwrap();
+    assert_eq!(ranked_cluster_labels[0].len(), 3);
+}
+
+#[test]
+fn test_builder_rejects_invalid_settings() {
+    let result = EncoderModelBuilder::new().top_n(0).build();
+    assert!(matches!(result, Err(EncoderError::InvalidArgument(_))));
+
+    let result = EncoderModelBuilder::new().inter_op_threads(0).build();
+    assert!(matches!(result, Err(EncoderError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_builder_reports_missing_assets() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let result = EncoderModelBuilder::new().assets_path(dir.path().to_str().unwrap()).build();
+    assert!(matches!(result, Err(EncoderError::MissingAssets(_))));
+}
@@ -0,0 +1,11 @@
+use cheminee_similarity_model::cancel::CancellationToken;
+
+#[test]
+fn test_cancellation_is_shared_between_clones() {
+    let token = CancellationToken::new();
+    let handle = token.clone();
+    assert!(!token.is_cancelled());
+
+    std::thread::spawn(move || handle.cancel()).join().unwrap();
+    assert!(token.is_cancelled());
+}
@@ -0,0 +1,140 @@
+use cheminee_similarity_model::centroids::{
+    load_centroid_metadata, load_centroids_csv, load_centroids_from_rows, load_centroids_npy,
+    CentroidLayout, CentroidSampling,
+};
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::npy::NpyWriter;
+use std::io::Write;
+
+fn write_centroid_file(rows: &[&str]) -> tempfile::NamedTempFile {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    for row in rows {
+        writeln!(file, "{}", row).unwrap();
+    }
+    file
+}
+
+#[test]
+fn test_load_centroids_with_radius_column() {
+    let file = write_centroid_file(&["0.5, 1.0, 2.0, 3.0", "0.25, 4.0, 5.0, 6.0"]);
+    let layout = CentroidLayout {
+        coordinate_columns: None,
+        radius_column: Some(0),
+    };
+
+    let centroids = load_centroids_csv(file.path().to_str().unwrap(), &layout, 3).unwrap();
+
+    assert_eq!(centroids.num_clusters(), 2);
+    assert_eq!(centroids.dim(), 3);
+    assert_eq!(centroids.coordinates.as_slice().unwrap(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    assert_eq!(centroids.radii, Some(vec![0.5, 0.25]));
+}
+
+#[test]
+fn test_load_centroids_rejects_latent_dim_mismatch() {
+    let file = write_centroid_file(&["1.0, 2.0, 3.0, 0.5"]);
+    let layout = CentroidLayout {
+        coordinate_columns: Some(vec![0, 1]),
+        radius_column: Some(3),
+    };
+
+    assert!(matches!(
+        load_centroids_csv(file.path().to_str().unwrap(), &layout, 3),
+        Err(EncoderError::ShapeMismatch(_))
+    ));
+}
+
+#[test]
+fn test_load_centroids_reports_parse_errors() {
+    let file = write_centroid_file(&["1.0, 2.0", "3.0, not-a-number"]);
+
+    assert!(matches!(
+        load_centroids_csv(file.path().to_str().unwrap(), &CentroidLayout::default(), 2),
+        Err(EncoderError::CentroidParse(_))
+    ));
+
+    let file = write_centroid_file(&["1.0, 2.0", "", "3.0, 4.0, 5.0"]);
+    assert!(matches!(
+        load_centroids_csv(file.path().to_str().unwrap(), &CentroidLayout::default(), 2),
+        Err(EncoderError::CentroidParse(_))
+    ));
+
+    let file = write_centroid_file(&["", "  "]);
+    assert!(matches!(
+        load_centroids_csv(file.path().to_str().unwrap(), &CentroidLayout::default(), 2),
+        Err(EncoderError::CentroidParse(_))
+    ));
+}
+
+#[test]
+fn test_load_centroids_npy() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("centroids.npy");
+    let path = path.to_str().unwrap();
+
+    let mut writer = NpyWriter::create(path, 2).unwrap();
+    writer.write_rows(&[0.0, 0.5, 1.0, 1.5, 2.0, 2.5]).unwrap();
+    writer.finish().unwrap();
+
+    let centroids = load_centroids_npy(path).unwrap();
+    assert_eq!(centroids.coordinates.dim(), (3, 2));
+    assert_eq!(centroids.coordinates.as_slice().unwrap(), &[0.0, 0.5, 1.0, 1.5, 2.0, 2.5]);
+    assert!(centroids.radii.is_none());
+}
+
+#[test]
+fn test_subsample_centroids() {
+    let file = write_centroid_file(&["0.0, 0.0", "1.0, 1.0", "2.0, 2.0", "3.0, 3.0", "4.0, 4.0"]);
+    let centroids =
+        load_centroids_csv(file.path().to_str().unwrap(), &CentroidLayout::default(), 2).unwrap();
+
+    let (coordinates, cluster_ids) = centroids
+        .subsample(&CentroidSampling::Strided { step: 2 })
+        .unwrap();
+    assert_eq!(cluster_ids, vec![0, 2, 4]);
+    assert_eq!(coordinates.as_slice().unwrap(), &[0.0, 0.0, 2.0, 2.0, 4.0, 4.0]);
+
+    let sampling = CentroidSampling::Random { count: 3, seed: 42 };
+    let (_, first_ids) = centroids.subsample(&sampling).unwrap();
+    let (_, second_ids) = centroids.subsample(&sampling).unwrap();
+    assert_eq!(first_ids.len(), 3);
+    assert_eq!(first_ids, second_ids);
+}
+
+#[test]
+fn test_load_centroids_from_rows() {
+    let rows = (0..3).map(|i| vec![i as f32, i as f32 + 0.5]);
+    let centroids = load_centroids_from_rows(rows).unwrap();
+    assert_eq!(centroids.dim(), (3, 2));
+    assert_eq!(centroids.as_slice().unwrap(), &[0.0, 0.5, 1.0, 1.5, 2.0, 2.5]);
+
+    let ragged = vec![vec![1.0, 2.0], vec![3.0]].into_iter();
+    assert!(matches!(
+        load_centroids_from_rows(ragged),
+        Err(EncoderError::ShapeMismatch(_))
+    ));
+}
+
+#[test]
+fn test_load_centroid_metadata_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    let centroids_path = dir.path().join("centroids.csv");
+    std::fs::write(&centroids_path, "1.0, 2.0\n").unwrap();
+    let centroids_path = centroids_path.to_str().unwrap();
+
+    assert_eq!(load_centroid_metadata(centroids_path).unwrap(), None);
+
+    std::fs::write(dir.path().join("centroids.json"), r#"{"temperature": 0.25}"#).unwrap();
+    let metadata = load_centroid_metadata(centroids_path).unwrap().unwrap();
+    assert_eq!(metadata.temperature, Some(0.25));
+}
+
+#[test]
+fn test_find_duplicate_centroids() {
+    let file = write_centroid_file(&["0.0, 0.0", "5.0, 5.0", "0.0, 0.001", "9.0, 1.0", "5.0, 5.0"]);
+    let centroids =
+        load_centroids_csv(file.path().to_str().unwrap(), &CentroidLayout::default(), 2).unwrap();
+
+    assert_eq!(centroids.find_duplicates(0.01), vec![(0, 2), (1, 4)]);
+    assert_eq!(centroids.find_duplicates(0.0), vec![(1, 4)]);
+}
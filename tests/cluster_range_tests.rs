@@ -0,0 +1,9 @@
+use cheminee_similarity_model::encoder::cluster_ranges;
+
+#[test]
+fn test_cluster_ranges_merge_adjacent_ids() {
+    let ranges = cluster_ranges([7, 3, 4, 9, 5, 3, 8, 12]);
+    assert_eq!(ranges, vec![(3, 5), (7, 9), (12, 12)]);
+
+    assert!(cluster_ranges([]).is_empty());
+}
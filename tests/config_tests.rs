@@ -0,0 +1,81 @@
+use cheminee_similarity_model::ann::HnswConfig;
+use cheminee_similarity_model::config::{ApproxTopK, EncoderConfig};
+use cheminee_similarity_model::encoder::{build_encoder_model_with_config, init_encoder_model};
+use cheminee_similarity_model::error::EncoderError;
+
+#[test]
+fn test_default_config_is_valid() {
+    EncoderConfig::default().validate().unwrap();
+}
+
+#[test]
+fn test_validate_reports_every_problem() {
+    let mut config = EncoderConfig {
+        top_n: Some(0),
+        approx_top_k: Some(ApproxTopK {
+            k: 10,
+            recall_target: 1.5,
+        }),
+        hnsw: Some(HnswConfig {
+            ef_search: 0,
+            ..HnswConfig::default()
+        }),
+        ..EncoderConfig::default()
+    };
+    config.session.gpu_memory_fraction = Some(0.0);
+
+    let Err(EncoderError::InvalidArgument(message)) = config.validate() else {
+        panic!("expected an invalid config");
+    };
+    assert!(message.contains("top_n"));
+    assert!(message.contains("recall_target"));
+    assert!(message.contains("ef_search"));
+    assert!(message.contains("GPU memory fraction"));
+
+    let result = build_encoder_model_with_config(config);
+    assert!(matches!(result, Err(EncoderError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_init_encoder_model() {
+    let encoder_model = init_encoder_model(EncoderConfig::default()).unwrap();
+    assert!(encoder_model.num_clusters() > 0);
+    assert!(encoder_model.cluster_usage_counts().iter().all(|count| *count == 0));
+}
+
+#[test]
+fn test_embedded_centroids_are_opt_in() {
+    let config = EncoderConfig::default();
+    assert!(!config.embedded_centroids);
+
+    let config = EncoderConfig {
+        embedded_centroids: true,
+        ..EncoderConfig::default()
+    };
+    assert_eq!(config.validate().is_ok(), cfg!(feature = "embedded-centroids"));
+}
+
+#[test]
+fn test_bfloat16_needs_tensorflow_encoder() {
+    let config = EncoderConfig {
+        bfloat16: true,
+        ..EncoderConfig::default()
+    };
+    assert_eq!(config.validate().is_ok(), cfg!(feature = "tensorflow"));
+}
+
+#[cfg(feature = "tract")]
+#[test]
+fn test_bfloat16_rejected_for_tract_encoder() {
+    use cheminee_similarity_model::config::EncoderBackend;
+
+    let config = EncoderConfig {
+        bfloat16: true,
+        encoder_backend: EncoderBackend::Tract,
+        ..EncoderConfig::default()
+    };
+    let Err(EncoderError::InvalidArgument(message)) = config.validate() else {
+        panic!("bfloat16 with the tract encoder should be rejected");
+    };
+    assert!(message.contains("bfloat16"));
+}
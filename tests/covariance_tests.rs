@@ -0,0 +1,67 @@
+use cheminee_similarity_model::covariance::{load_cluster_covariance, ClusterCovariance};
+use cheminee_similarity_model::error::EncoderError;
+use ndarray::{Array2, Array3};
+use ndarray_npy::write_npy;
+
+fn rms_distance(a: &[f32], b: &[f32]) -> f32 {
+    let squared: f32 = a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum();
+    (squared / a.len() as f32).sqrt()
+}
+
+#[test]
+fn test_diagonal_covariance_scales_distance() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("covariance.npy");
+    let variances = Array2::from_shape_vec((2, 2), vec![4.0f32, 4.0, 1.0, 1.0]).unwrap();
+    write_npy(&path, &variances).unwrap();
+
+    let covariance = load_cluster_covariance(path.to_str().unwrap(), 2, 2).unwrap();
+    assert!(matches!(covariance, ClusterCovariance::Diagonal { .. }));
+    assert_eq!(covariance.num_clusters(), 2);
+    assert_eq!(covariance.dim(), 2);
+
+    let centroid = [0.0, 0.0];
+    let latent = [2.0, 4.0];
+    let euclidean = rms_distance(&centroid, &latent);
+    assert!((covariance.distance(0, &centroid, &latent) - euclidean / 2.0).abs() < 1e-6);
+    assert!((covariance.distance(1, &centroid, &latent) - euclidean).abs() < 1e-6);
+}
+
+#[test]
+fn test_identity_full_covariance_matches_euclidean() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("covariance.npy");
+    let mut identities = Array3::<f32>::zeros((1, 3, 3));
+    for i in 0..3 {
+        identities[[0, i, i]] = 1.0;
+    }
+    write_npy(&path, &identities).unwrap();
+
+    let covariance = load_cluster_covariance(path.to_str().unwrap(), 1, 3).unwrap();
+    assert!(matches!(covariance, ClusterCovariance::Full { .. }));
+
+    let centroid = [1.0, -1.0, 0.5];
+    let latent = [0.0, 2.0, 0.5];
+    let distance = covariance.distance(0, &centroid, &latent);
+    assert!((distance - rms_distance(&centroid, &latent)).abs() < 1e-6);
+}
+
+#[test]
+fn test_rejects_invalid_covariance() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let diagonal_path = dir.path().join("diagonal.npy");
+    let variances = Array2::from_shape_vec((1, 2), vec![1.0f32, -1.0]).unwrap();
+    write_npy(&diagonal_path, &variances).unwrap();
+    let result = load_cluster_covariance(diagonal_path.to_str().unwrap(), 1, 2);
+    assert!(matches!(result, Err(EncoderError::CentroidParse(_))));
+
+    let full_path = dir.path().join("full.npy");
+    let not_positive_definite = Array3::from_shape_vec((1, 2, 2), vec![1.0f32, 2.0, 2.0, 1.0]).unwrap();
+    write_npy(&full_path, &not_positive_definite).unwrap();
+    let result = load_cluster_covariance(full_path.to_str().unwrap(), 1, 2);
+    assert!(matches!(result, Err(EncoderError::CentroidParse(_))));
+
+    let result = load_cluster_covariance(diagonal_path.to_str().unwrap(), 2, 2);
+    assert!(result.is_err());
+}
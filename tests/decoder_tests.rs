@@ -0,0 +1,75 @@
+use cheminee_similarity_model::decoder::{build_decoder_model, build_decoder_model_from_path};
+use cheminee_similarity_model::encoder::build_encoder_model;
+use cheminee_similarity_model::error::EncoderError;
+use ndarray::Array2;
+
+fn fingerprint() -> Vec<i64> {
+    let mut fingerprint = vec![0; 2048];
+    for bit in [1, 11, 41, 80, 650, 1023, 1380, 2047] {
+        fingerprint[bit] = 1;
+    }
+    fingerprint
+}
+
+#[test]
+fn test_missing_decoder_is_reported() {
+    let assets_dir = tempfile::tempdir().unwrap();
+    let result = build_decoder_model_from_path(assets_dir.path().to_str().unwrap());
+
+    assert!(matches!(result, Err(EncoderError::MissingAssets(_))));
+}
+
+#[test]
+fn test_decode_output_shape() {
+    let decoder_model = build_decoder_model().unwrap();
+    let latent_dim = decoder_model.latent_dim().unwrap_or(128);
+
+    let probabilities = decoder_model.decode(&Array2::zeros((3, latent_dim))).unwrap();
+    assert_eq!(probabilities.nrows(), 3);
+    assert_eq!(probabilities.ncols(), decoder_model.output_dim().unwrap_or(2048));
+    assert!(probabilities.iter().all(|probability| (0.0..=1.0).contains(probability)));
+
+    assert!(matches!(
+        decoder_model.decode(&Array2::zeros((0, latent_dim))),
+        Err(EncoderError::ShapeMismatch(_))
+    ));
+    assert!(matches!(
+        decoder_model.decode(&Array2::zeros((1, latent_dim + 1))),
+        Err(EncoderError::ShapeMismatch(_))
+    ));
+}
+
+#[test]
+fn test_encode_decode_round_trip() {
+    let encoder_model = build_encoder_model().unwrap();
+    let decoder_model = build_decoder_model().unwrap();
+    let fingerprint = fingerprint();
+
+    let latents = encoder_model.encode_latent(&[fingerprint.clone()]).unwrap();
+    let probabilities = decoder_model.decode(&latents).unwrap();
+    assert_eq!(probabilities.ncols(), fingerprint.len());
+
+    // Set bits should come back more likely than unset ones
+    let (mut set, mut unset) = (Vec::new(), Vec::new());
+    for (&bit, &probability) in fingerprint.iter().zip(probabilities.row(0)) {
+        if bit == 1 {
+            set.push(probability);
+        } else {
+            unset.push(probability);
+        }
+    }
+    let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+    assert!(mean(&set) > mean(&unset));
+}
+
+#[test]
+fn test_outlier_scores_with_decoder() {
+    let encoder_model = build_encoder_model().unwrap();
+    let decoder_model = build_decoder_model().unwrap();
+
+    let (_, scores) = encoder_model.outlier_scores_with_decoder(&fingerprint(), &decoder_model).unwrap();
+    let (_, plain_scores) = encoder_model.outlier_scores(&fingerprint()).unwrap();
+    assert!(scores.reconstruction_error.is_some_and(|error| error.is_finite() && error >= 0.0));
+    assert_eq!(plain_scores.reconstruction_error, None);
+    assert!((scores.nearest_distance - plain_scores.nearest_distance).abs() < 1e-5);
+}
@@ -0,0 +1,15 @@
+#![cfg(feature = "download")]
+
+use cheminee_similarity_model::download::verify_sha256;
+use cheminee_similarity_model::error::EncoderError;
+
+#[test]
+fn test_verify_sha256() {
+    let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    assert!(verify_sha256(b"hello", digest).is_ok());
+    assert!(verify_sha256(b"hello", &digest.to_uppercase()).is_ok());
+    assert!(matches!(
+        verify_sha256(b"hello!", digest),
+        Err(EncoderError::ChecksumMismatch { .. })
+    ));
+}
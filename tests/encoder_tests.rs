@@ -1,4 +1,17 @@
-use cheminee_similarity_model::encoder::build_encoder_model;
+use cheminee_similarity_model::centroids::{load_centroids_from_rows, Centroids};
+use cheminee_similarity_model::config::{ApproxTopK, EncoderConfig};
+use cheminee_similarity_model::encoder::{build_encoder_model, build_encoder_model_with_config, prefetch_centroids, EncoderModel};
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::fingerprint::{pack_fingerprints, PackedFingerprints};
+use cheminee_similarity_model::metric::DistanceMetric;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_encoder_model_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<EncoderModel>();
+}
 
 #[test]
 fn test_encode() {
@@ -80,3 +93,250 @@ fn test_encode() {
     assert_eq!(ranked_cluster_labels[1][0], 8130);
 }
 
+
+#[test]
+fn test_concurrent_transform_on_shared_model() {
+    let encoder_model = Arc::new(build_encoder_model().unwrap());
+    let inputs = (0..8)
+        .map(|seed| {
+            let mut row = vec![0; 2048];
+            for bit in (seed..2048).step_by(97) {
+                row[bit] = 1;
+            }
+            vec![row]
+        })
+        .collect::<Vec<Vec<Vec<i64>>>>();
+    let expected = inputs
+        .iter()
+        .map(|input_data| encoder_model.transform(input_data).unwrap())
+        .collect::<Vec<Vec<Vec<i32>>>>();
+
+    let handles = inputs
+        .into_iter()
+        .map(|input_data| {
+            let encoder_model = Arc::clone(&encoder_model);
+            thread::spawn(move || (0..4).map(|_| encoder_model.transform(&input_data).unwrap()).collect::<Vec<_>>())
+        })
+        .collect::<Vec<_>>();
+
+    for (handle, expected) in handles.into_iter().zip(expected) {
+        assert!(handle.join().unwrap().iter().all(|labels| *labels == expected));
+    }
+}
+
+#[test]
+fn test_transform_packed_matches_transform() {
+    let mut input_data = vec![vec![0; 2048], vec![0; 2048]];
+    for bit in [1, 11, 41, 80, 1023, 2047] {
+        input_data[0][bit] = 1;
+    }
+    for bit in [0, 63, 64, 500] {
+        input_data[1][bit] = 1;
+    }
+
+    let encoder_model = build_encoder_model().unwrap();
+    let words = pack_fingerprints(&input_data);
+    let fingerprints = PackedFingerprints::new(&words, 2048).unwrap();
+
+    assert_eq!(
+        encoder_model.transform_packed(&fingerprints).unwrap(),
+        encoder_model.transform(&input_data).unwrap()
+    );
+}
+
+#[test]
+fn test_transform_rejects_malformed_input() {
+    let encoder_model = build_encoder_model().unwrap();
+
+    let empty: Vec<Vec<i64>> = Vec::new();
+    assert!(matches!(encoder_model.transform(&empty), Err(EncoderError::ShapeMismatch(_))));
+
+    let ragged = vec![vec![0; 2048], vec![0; 2047]];
+    assert!(matches!(encoder_model.transform(&ragged), Err(EncoderError::ShapeMismatch(_))));
+
+    let too_short = vec![vec![0; 16]];
+    assert!(matches!(encoder_model.transform(&too_short), Err(EncoderError::ShapeMismatch(_))));
+}
+
+#[test]
+fn test_warm_up_leaves_usage_counts_untouched() {
+    let config = EncoderConfig {
+        track_usage: true,
+        ..EncoderConfig::default()
+    };
+    let encoder_model = build_encoder_model_with_config(config).unwrap();
+
+    encoder_model.warm_up().unwrap();
+    assert!(encoder_model.cluster_usage_counts().iter().all(|count| *count == 0));
+}
+
+#[test]
+fn test_only_transform_entry_points_count_usage() {
+    let config = EncoderConfig {
+        track_usage: true,
+        top_n: Some(3),
+        ..EncoderConfig::default()
+    };
+    let encoder_model = build_encoder_model_with_config(config).unwrap();
+    let input_data = vec![vec![0; 2048], vec![1; 2048]];
+
+    let weighted_clusters = encoder_model.rank_weights(&input_data, 5, 0.5).unwrap();
+    assert!(weighted_clusters.iter().all(|row| row.len() == 5));
+    assert!(!encoder_model.batch_cluster_ranges(&input_data, 5).unwrap().is_empty());
+    encoder_model.effective_clusters(&input_data).unwrap();
+    encoder_model.assignment_confusion(&input_data, &[0, 1]).unwrap();
+    assert_eq!(encoder_model.cluster_usage_counts().iter().sum::<u64>(), 0);
+
+    let ranked_cluster_labels = encoder_model.transform_variable_k(&input_data, &[5, 1]).unwrap();
+    assert_eq!(ranked_cluster_labels[0].len(), 5);
+    assert_eq!(ranked_cluster_labels[1].len(), 1);
+    assert_eq!(encoder_model.cluster_usage_counts().iter().sum::<u64>(), 2);
+
+    assert!(encoder_model.transform(&input_data).unwrap().iter().all(|row| row.len() == 3));
+    assert_eq!(encoder_model.cluster_usage_counts().iter().sum::<u64>(), 4);
+}
+
+#[test]
+fn test_approx_top_k_state_is_per_model() {
+    let config = EncoderConfig {
+        approx_top_k: Some(ApproxTopK {
+            k: 3,
+            recall_target: 0.95,
+        }),
+        ..EncoderConfig::default()
+    };
+    let approximate_model = build_encoder_model_with_config(config).unwrap();
+    let exact_model = build_encoder_model().unwrap();
+    let input_data = vec![vec![0; 2048], vec![1; 2048]];
+
+    // Cached graphs are reused across calls, with or without `ApproxTopK` in the linked TF build
+    for _ in 0..2 {
+        let ranked_cluster_labels = approximate_model.transform(&input_data).unwrap();
+        assert!(ranked_cluster_labels.iter().all(|row| row.len() == 3));
+    }
+
+    let ranked_cluster_labels = exact_model.transform(&input_data).unwrap();
+    assert!(ranked_cluster_labels.iter().all(|row| row.len() == exact_model.num_clusters()));
+}
+
+#[test]
+fn test_models_own_their_centroids() {
+    let mut first_model = build_encoder_model().unwrap();
+    let second_model = build_encoder_model().unwrap();
+    let num_clusters = second_model.num_clusters();
+    let dim = second_model.centroids().dim();
+
+    let coordinates = first_model.centroids().coordinates.as_slice().unwrap()[..2 * dim].to_vec();
+    let centroids = Centroids {
+        coordinates: load_centroids_from_rows(coordinates.chunks(dim).map(<[f32]>::to_vec)).unwrap(),
+        radii: None,
+    };
+    first_model.update_centroids(centroids).unwrap();
+
+    assert_eq!(first_model.num_clusters(), 2);
+    assert_eq!(second_model.num_clusters(), num_clusters);
+    assert!(second_model.transform(&[vec![0; 2048]]).unwrap()[0].len() > 2);
+}
+
+#[test]
+fn test_assign_latents_matches_transform() {
+    let encoder_model = build_encoder_model().unwrap();
+    let input_data = vec![vec![0; 2048], vec![1; 2048]];
+
+    let latents = encoder_model.encode_latent(&input_data).unwrap();
+    let ranked_clusters = encoder_model.assign_latents(&latents, Some(5)).unwrap();
+    let ranked_cluster_labels = encoder_model.transform_top_n(&input_data, 5).unwrap();
+
+    for (ranked_row, label_row) in ranked_clusters.iter().zip(&ranked_cluster_labels) {
+        let labels = ranked_row.iter().map(|(label, _)| *label).collect::<Vec<i32>>();
+        assert_eq!(&labels, label_row);
+    }
+}
+
+#[test]
+fn test_similarity_matrix_uses_configured_metric() {
+    let set_a = vec![vec![0; 2048], vec![1; 2048]];
+    let mut set_b = vec![vec![0; 2048]];
+    for bit in [1, 11, 41, 80, 1023] {
+        set_b[0][bit] = 1;
+    }
+
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Manhattan] {
+        let config = EncoderConfig {
+            metric,
+            ..EncoderConfig::default()
+        };
+        let encoder_model = build_encoder_model_with_config(config).unwrap();
+
+        let similarities = encoder_model.similarity_matrix(&set_a, &set_b).unwrap();
+        assert_eq!(similarities.dim(), (2, 1));
+
+        let latents_a = encoder_model.encode_latent(&set_a).unwrap();
+        let latents_b = encoder_model.encode_latent(&set_b).unwrap();
+        for (row_idx, latent_a) in latents_a.rows().into_iter().enumerate() {
+            let distance = metric.distance(&latent_a.to_vec(), &latents_b.row(0).to_vec());
+            assert!((similarities[[row_idx, 0]] - 1.0 / (1.0 + distance)).abs() < 1e-4);
+        }
+    }
+}
+
+#[cfg(feature = "tensorflow")]
+#[test]
+fn test_exported_assignment_graph_matches_transform() {
+    use tensorflow::{Graph, ImportGraphDefOptions, Session, SessionOptions, SessionRunArgs, Tensor};
+
+    let input_data = vec![vec![0; 2048], vec![1; 2048]];
+    let dir = tempfile::tempdir().unwrap();
+
+    for metric in [DistanceMetric::Euclidean, DistanceMetric::Cosine, DistanceMetric::Manhattan] {
+        let config = EncoderConfig {
+            metric,
+            ..EncoderConfig::default()
+        };
+        let encoder_model = build_encoder_model_with_config(config).unwrap();
+        let num_clusters = encoder_model.num_clusters();
+
+        let graph_path = dir.path().join(format!("{:?}.pb", metric));
+        encoder_model.export_assignment_graph(graph_path.to_str().unwrap()).unwrap();
+
+        let mut graph = Graph::new();
+        graph
+            .import_graph_def(&std::fs::read(&graph_path).unwrap(), &ImportGraphDefOptions::new())
+            .unwrap();
+        let session = Session::new(&SessionOptions::new(), &graph).unwrap();
+
+        let latents = encoder_model.encode_latent(&input_data).unwrap();
+        let (rows, dim) = latents.dim();
+        let latent_tensor = Tensor::new(&[rows as u64, dim as u64])
+            .with_values(&latents.iter().copied().collect::<Vec<f32>>())
+            .unwrap();
+
+        let mut run_args = SessionRunArgs::new();
+        run_args.add_feed(&graph.operation_by_name_required("latent").unwrap(), 0, &latent_tensor);
+        let labels_token = run_args.request_fetch(&graph.operation_by_name_required("cluster_labels").unwrap(), 0);
+        session.run(&mut run_args).unwrap();
+        let labels: Tensor<i32> = run_args.fetch(labels_token).unwrap();
+
+        let ranked_cluster_labels = encoder_model.transform_top_n(&input_data, num_clusters).unwrap();
+        for (row_idx, label_row) in ranked_cluster_labels.iter().enumerate() {
+            let exported_row = &labels[row_idx * num_clusters..(row_idx + 1) * num_clusters];
+            assert_eq!(&exported_row[..5], &label_row[..5], "{:?}", metric);
+        }
+    }
+}
+
+#[test]
+fn test_prefetch_centroids_reads_configured_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("centroids.npy"), [0u8; 16]).unwrap();
+    let assets_path = dir.path().to_str().unwrap();
+
+    let config = EncoderConfig {
+        centroids_file: "centroids.npy".to_string(),
+        ..EncoderConfig::default()
+    };
+    prefetch_centroids(assets_path, &config).unwrap();
+
+    let result = prefetch_centroids(assets_path, &EncoderConfig::default());
+    assert!(matches!(result, Err(EncoderError::Io(_))));
+}
@@ -0,0 +1,42 @@
+use cheminee_similarity_model::ffi::{csw_free, csw_last_error, csw_model_load, csw_transform, CSW_NO_LABEL};
+use std::ffi::CStr;
+use std::ptr;
+
+#[test]
+fn test_ffi_load_transform_free() {
+    unsafe {
+        let model = csw_model_load(ptr::null());
+        assert!(!model.is_null());
+
+        let mut fingerprints = vec![0i64; 2 * 2048];
+        fingerprints[2048..].fill(1);
+        let mut labels = vec![CSW_NO_LABEL; 2 * 3];
+
+        let status = csw_transform(model, fingerprints.as_ptr(), 2, 2048, 3, labels.as_mut_ptr());
+        assert_eq!(status, 0);
+        assert!(labels.iter().all(|&label| label != CSW_NO_LABEL));
+
+        csw_free(model);
+    }
+}
+
+#[test]
+fn test_ffi_rejects_overflowing_sizes() {
+    unsafe {
+        let model = csw_model_load(ptr::null());
+        assert!(!model.is_null());
+
+        let fingerprints = [0i64; 1];
+        let mut labels = [0i32; 1];
+
+        let status = csw_transform(model, fingerprints.as_ptr(), usize::MAX, 2, 1, labels.as_mut_ptr());
+        assert_ne!(status, 0);
+        let message = CStr::from_ptr(csw_last_error()).to_str().unwrap();
+        assert!(message.contains("num_rows * num_bits"));
+
+        let status = csw_transform(model, fingerprints.as_ptr(), 2, 1, usize::MAX, labels.as_mut_ptr());
+        assert_ne!(status, 0);
+
+        csw_free(model);
+    }
+}
@@ -0,0 +1,59 @@
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::fingerprint::{pack_fingerprints, FingerprintKind, FingerprintSpec, PackedFingerprints};
+use std::borrow::Cow;
+
+fn spec(num_bits: usize) -> FingerprintSpec {
+    FingerprintSpec {
+        kind: FingerprintKind::Morgan,
+        radius: Some(2),
+        num_bits,
+    }
+}
+
+#[test]
+fn test_conform_passes_matching_rows_through() {
+    let input_data = vec![vec![0, 1, 1, 0], vec![1, 0, 0, 0]];
+
+    let conformed = spec(4).conform(&input_data).unwrap();
+    assert!(matches!(conformed, Cow::Borrowed(_)));
+    assert_eq!(conformed.as_ref(), input_data.as_slice());
+}
+
+#[test]
+fn test_conform_folds_wider_rows() {
+    let input_data = vec![vec![0, 1, 0, 0, 1, 0, 0, 1], vec![1, 0, 0, 0]];
+
+    let conformed = spec(4).conform(&input_data).unwrap();
+    assert_eq!(conformed.as_ref(), &[vec![1, 1, 0, 1], vec![1, 0, 0, 0]]);
+}
+
+#[test]
+fn test_conform_rejects_mismatched_rows() {
+    let result = spec(4).conform(&[vec![0, 1, 0, 0, 1, 0]]);
+    assert!(matches!(result, Err(EncoderError::ShapeMismatch(_))));
+
+    let result = spec(4).conform(&[vec![0, 1, 0, 0], vec![0, 3, 0, 0]]);
+    assert!(matches!(result, Err(EncoderError::InvalidArgument(message)) if message.starts_with("Row 1")));
+}
+
+#[test]
+fn test_packed_fingerprints_round_trip() {
+    let mut input_data = vec![vec![0; 70], vec![0; 70]];
+    input_data[0][0] = 1;
+    input_data[0][69] = 1;
+    input_data[1][64] = 1;
+
+    let words = pack_fingerprints(&input_data);
+    assert_eq!(words, vec![1, 1 << 5, 0, 1]);
+
+    let fingerprints = PackedFingerprints::new(&words, 70).unwrap();
+    assert_eq!(fingerprints.num_rows(), 2);
+    assert_eq!(fingerprints.unpack(), input_data);
+    assert_eq!(fingerprints.unpack_flat::<f32>()[69], 1.0);
+}
+
+#[test]
+fn test_packed_fingerprints_reject_partial_rows() {
+    let result = PackedFingerprints::new(&[0, 0, 0], 70);
+    assert!(matches!(result, Err(EncoderError::ShapeMismatch(_))));
+}
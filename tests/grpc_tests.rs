@@ -0,0 +1,92 @@
+#![cfg(feature = "grpc")]
+
+use cheminee_similarity_model::encoder::{build_encoder_model, EncoderModel};
+use cheminee_similarity_model::grpc::proto::cluster_assignment_client::ClusterAssignmentClient;
+use cheminee_similarity_model::grpc::proto::{AssignRequest, AssignResponse, Fingerprint};
+use cheminee_similarity_model::grpc::AssignService;
+use std::sync::Arc;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Channel, Server};
+use tonic::{Code, Status};
+
+async fn client(model: Arc<EncoderModel>) -> ClusterAssignmentClient<Channel> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+
+    tokio::spawn(
+        Server::builder()
+            .add_service(AssignService::new(model).into_server())
+            .serve_with_incoming(incoming),
+    );
+
+    ClusterAssignmentClient::connect(format!("http://{}", addr)).await.unwrap()
+}
+
+fn request(fingerprints: Vec<Vec<i64>>, top_n: u32) -> AssignRequest {
+    AssignRequest {
+        fingerprints: fingerprints.into_iter().map(|bits| Fingerprint { bits }).collect(),
+        top_n,
+    }
+}
+
+async fn assign(
+    client: &mut ClusterAssignmentClient<Channel>,
+    requests: Vec<AssignRequest>,
+) -> Vec<Result<AssignResponse, Status>> {
+    let mut responses = client.assign(tokio_stream::iter(requests)).await.unwrap().into_inner();
+
+    let mut replies = Vec::new();
+    loop {
+        match responses.message().await {
+            Ok(Some(response)) => replies.push(Ok(response)),
+            Ok(None) => break,
+            Err(status) => {
+                replies.push(Err(status));
+                break;
+            }
+        }
+    }
+    replies
+}
+
+#[tokio::test]
+async fn test_assign_streams_replies_in_order() {
+    let model = Arc::new(build_encoder_model().unwrap());
+    let mut client = client(model.clone()).await;
+    let first = vec![vec![0; 2048]];
+    let second = vec![vec![1; 2048], vec![0; 2048]];
+
+    let replies = assign(&mut client, vec![request(first.clone(), 0), request(second.clone(), 3)]).await;
+    assert_eq!(replies.len(), 2);
+
+    for (reply, (fingerprints, top_n)) in replies.into_iter().zip([(first, 1), (second, 3)]) {
+        let expected = model.transform_assignments(&fingerprints, top_n).unwrap();
+        let labels = reply
+            .unwrap()
+            .assignments
+            .into_iter()
+            .map(|assignment| assignment.labels)
+            .collect::<Vec<Vec<i32>>>();
+        assert_eq!(labels, expected.into_iter().map(|assignment| assignment.labels).collect::<Vec<_>>());
+    }
+}
+
+#[tokio::test]
+async fn test_assign_empty_request_gets_empty_reply() {
+    let mut client = client(Arc::new(build_encoder_model().unwrap())).await;
+
+    let replies = assign(&mut client, vec![request(Vec::new(), 1)]).await;
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].as_ref().unwrap().assignments.is_empty());
+}
+
+#[tokio::test]
+async fn test_assign_ragged_request_ends_stream_with_invalid_argument() {
+    let mut client = client(Arc::new(build_encoder_model().unwrap())).await;
+
+    let requests = vec![request(vec![vec![0; 2048], vec![0; 2047]], 1), request(vec![vec![0; 2048]], 1)];
+    let replies = assign(&mut client, requests).await;
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].as_ref().unwrap_err().code(), Code::InvalidArgument);
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "metrics")]
+
+use cheminee_similarity_model::encoder::build_encoder_model;
+use cheminee_similarity_model::instrumentation::{ASSIGN_SECONDS, BATCH_SIZE, ENCODE_SECONDS, ERRORS_TOTAL, INFERENCES_TOTAL};
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+#[test]
+fn test_transform_records_metrics() {
+    let encoder_model = build_encoder_model().unwrap();
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        encoder_model.transform(&[vec![0; 2048], vec![0; 2048]]).unwrap();
+        assert!(encoder_model.transform(&[vec![0; 16]]).is_err());
+    });
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let value = |name: &str| {
+        snapshot
+            .iter()
+            .find(|(key, _, _, _)| key.key().name() == name)
+            .map(|(_, _, _, value)| value.clone())
+    };
+
+    assert_eq!(value(INFERENCES_TOTAL), Some(DebugValue::Counter(3)));
+    assert!(matches!(value(BATCH_SIZE), Some(DebugValue::Histogram(sizes)) if sizes.len() == 2));
+    assert!(matches!(value(ENCODE_SECONDS), Some(DebugValue::Histogram(_))));
+    assert!(matches!(value(ASSIGN_SECONDS), Some(DebugValue::Histogram(latencies)) if latencies.len() == 1));
+    assert_eq!(value(ERRORS_TOTAL), Some(DebugValue::Counter(1)));
+}
@@ -0,0 +1,34 @@
+use cheminee_similarity_model::kernel::{WeightClamp, WeightKernel};
+use std::sync::Arc;
+
+#[test]
+fn test_builtin_kernels_decrease_with_distance() {
+    let kernels = [
+        WeightKernel::Exponential { temperature: 0.5 },
+        WeightKernel::Gaussian { bandwidth: 0.5 },
+        WeightKernel::InverseDistance { epsilon: 1e-3 },
+    ];
+
+    for kernel in kernels {
+        assert!(kernel.weight(0.1) > kernel.weight(0.2), "{:?}", kernel);
+    }
+}
+
+#[test]
+fn test_custom_kernel_can_peak_away_from_zero() {
+    let kernel = WeightKernel::Custom(Arc::new(|distance: f32| (-(distance - 1.0).powi(2)).exp()));
+
+    assert!(kernel.weight(1.0) > kernel.weight(0.0));
+}
+
+#[test]
+fn test_weight_clamp() {
+    let clamp = WeightClamp {
+        floor: Some(1e-6),
+        precision: Some(3),
+    };
+
+    assert_eq!(clamp.apply(1e-30), 0.0);
+    assert!((clamp.apply(0.12345) - 0.123).abs() < 1e-7);
+    assert_eq!(WeightClamp::default().apply(1e-30), 1e-30);
+}
@@ -0,0 +1,111 @@
+use cheminee_similarity_model::centroids::{load_centroids_csv, save_centroids_csv, CentroidLayout};
+use cheminee_similarity_model::kmeans::{fit_kmeans, KMeansConfig, MiniBatchKMeans};
+use ndarray::Array2;
+
+// Two tight blobs around (0, 0) and (10, 10)
+fn two_blobs() -> Array2<f32> {
+    let rows = (0..20)
+        .flat_map(|i| {
+            let offset = if i % 2 == 0 { 0.0 } else { 10.0 };
+            let jitter = (i / 2) as f32 * 0.01;
+            [offset + jitter, offset - jitter]
+        })
+        .collect::<Vec<f32>>();
+
+    Array2::from_shape_vec((20, 2), rows).unwrap()
+}
+
+#[test]
+fn test_fit_kmeans_separates_blobs() {
+    let config = KMeansConfig {
+        k: 2,
+        ..KMeansConfig::default()
+    };
+    let fit = fit_kmeans(&two_blobs(), &config).unwrap();
+    assert!(fit.converged);
+
+    let mut centroids = fit.centroids.coordinates.rows().into_iter().map(|row| row[0]).collect::<Vec<f32>>();
+    centroids.sort_by(f32::total_cmp);
+    assert!((centroids[0] - 0.045).abs() < 1e-4);
+    assert!((centroids[1] - 10.045).abs() < 1e-4);
+
+    let radii = fit.centroids.radii.as_ref().unwrap();
+    assert!(radii.iter().all(|radius| *radius > 0.0 && *radius < 0.1));
+}
+
+#[test]
+fn test_fit_kmeans_is_deterministic_per_seed() {
+    let config = KMeansConfig {
+        k: 3,
+        seed: 7,
+        ..KMeansConfig::default()
+    };
+    let a = fit_kmeans(&two_blobs(), &config).unwrap();
+    let b = fit_kmeans(&two_blobs(), &config).unwrap();
+
+    assert_eq!(a.centroids.coordinates, b.centroids.coordinates);
+}
+
+#[test]
+fn test_fit_kmeans_rejects_k_above_rows() {
+    let config = KMeansConfig {
+        k: 21,
+        ..KMeansConfig::default()
+    };
+    assert!(fit_kmeans(&two_blobs(), &config).is_err());
+}
+
+#[test]
+fn test_saved_centroids_round_trip() {
+    let config = KMeansConfig {
+        k: 2,
+        ..KMeansConfig::default()
+    };
+    let fit = fit_kmeans(&two_blobs(), &config).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("centroids.csv");
+    save_centroids_csv(&fit.centroids, path.to_str().unwrap()).unwrap();
+
+    let layout = CentroidLayout {
+        radius_column: Some(2),
+        ..CentroidLayout::default()
+    };
+    let loaded = load_centroids_csv(path.to_str().unwrap(), &layout, 2).unwrap();
+
+    assert_eq!(loaded.coordinates, fit.centroids.coordinates);
+    assert_eq!(loaded.radii, fit.centroids.radii);
+}
+
+#[test]
+fn test_mini_batch_moves_centroids_toward_batch_mean() {
+    let config = KMeansConfig {
+        k: 2,
+        ..KMeansConfig::default()
+    };
+    let fit = fit_kmeans(&two_blobs(), &config).unwrap();
+    let mut mini_batch = MiniBatchKMeans::new(&fit.centroids, 0.5).unwrap();
+
+    // Every latent lands near the (0, 0) blob, so only that centroid moves, halfway to (1, -1)
+    let batch = Array2::from_shape_vec((2, 2), vec![1.0, -1.0, 1.0, -1.0]).unwrap();
+    let shift = mini_batch.partial_fit(&batch).unwrap();
+    assert!(shift > 0.0);
+
+    let updated = mini_batch.centroids().unwrap();
+    let moved = updated.coordinates.rows().into_iter().find(|row| row[0] < 5.0).unwrap();
+    assert!((moved[0] - 0.5225).abs() < 1e-4);
+    assert!((moved[1] + 0.5225).abs() < 1e-4);
+    assert_eq!(updated.radii, fit.centroids.radii);
+}
+
+#[test]
+fn test_mini_batch_rejects_invalid_learning_rate() {
+    let config = KMeansConfig {
+        k: 2,
+        ..KMeansConfig::default()
+    };
+    let fit = fit_kmeans(&two_blobs(), &config).unwrap();
+
+    assert!(MiniBatchKMeans::new(&fit.centroids, 0.0).is_err());
+    assert!(MiniBatchKMeans::new(&fit.centroids, 1.5).is_err());
+}
@@ -0,0 +1,48 @@
+use cheminee_similarity_model::ann::HnswConfig;
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::latent_index::LatentIndex;
+use cheminee_similarity_model::metric::DistanceMetric;
+
+fn corpus() -> (Vec<String>, Vec<f32>) {
+    let ids = (0..20).map(|i| format!("mol-{}", i)).collect();
+    let latents = (0..20).flat_map(|i| [i as f32, -(i as f32)]).collect();
+    (ids, latents)
+}
+
+#[test]
+fn test_latent_index_search() {
+    let (ids, latents) = corpus();
+    let index = LatentIndex::build(ids, latents, 2, &HnswConfig::default(), DistanceMetric::Euclidean).unwrap();
+    assert_eq!(index.len(), 20);
+
+    let neighbours = index.search(&[7.1, -7.1], 2).unwrap();
+    assert_eq!(neighbours[0].0, "mol-7");
+    assert_eq!(neighbours[1].0, "mol-8");
+    assert!(neighbours[0].1 < neighbours[1].1);
+
+    assert!(matches!(index.search(&[1.0], 1), Err(EncoderError::ShapeMismatch(_))));
+}
+
+#[test]
+fn test_latent_index_round_trip() {
+    let (ids, latents) = corpus();
+    let index = LatentIndex::build(ids, latents, 2, &HnswConfig::default(), DistanceMetric::Euclidean).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index");
+    index.save(path.to_str().unwrap()).unwrap();
+
+    let loaded = LatentIndex::load(path.to_str().unwrap(), &HnswConfig::default(), DistanceMetric::Manhattan).unwrap();
+    assert_eq!(loaded.len(), 20);
+    assert_eq!(loaded.dim(), 2);
+    assert_eq!(loaded.search(&[3.0, -3.0], 1).unwrap()[0].0, "mol-3");
+}
+
+#[test]
+fn test_latent_index_rejects_mismatched_ids() {
+    let (mut ids, latents) = corpus();
+    ids.pop();
+
+    let result = LatentIndex::build(ids, latents, 2, &HnswConfig::default(), DistanceMetric::Euclidean);
+    assert!(matches!(result, Err(EncoderError::ShapeMismatch(_))));
+}
@@ -0,0 +1,48 @@
+use cheminee_similarity_model::fingerprint::{FingerprintKind, FingerprintSpec};
+use cheminee_similarity_model::manifest::{date_stamp, load_asset_manifest, AssetManifest, MANIFEST_FILE};
+
+#[test]
+fn test_date_stamp() {
+    assert_eq!(date_stamp("lf_kmeans_10k_centroids_20241111.csv"), Some("20241111".to_string()));
+    assert_eq!(date_stamp("assets/v2_20230101_20240505.csv"), Some("20240505".to_string()));
+    assert_eq!(date_stamp("vae_encoder"), None);
+    assert_eq!(date_stamp("centroids_123456789.csv"), None);
+}
+
+#[test]
+fn test_load_asset_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let assets_path = dir.path().to_str().unwrap();
+
+    assert_eq!(load_asset_manifest(assets_path).unwrap(), None);
+
+    std::fs::write(dir.path().join(MANIFEST_FILE), r#"{"model_version": "vae-3"}"#).unwrap();
+    assert_eq!(
+        load_asset_manifest(assets_path).unwrap(),
+        Some(AssetManifest {
+            model_version: Some("vae-3".to_string()),
+            centroid_version: None,
+            fingerprint: None,
+        })
+    );
+}
+
+#[test]
+fn test_load_asset_manifest_with_fingerprint() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join(MANIFEST_FILE),
+        r#"{"fingerprint": {"kind": "morgan", "radius": 2, "num_bits": 2048}}"#,
+    )
+    .unwrap();
+
+    let manifest = load_asset_manifest(dir.path().to_str().unwrap()).unwrap().unwrap();
+    assert_eq!(
+        manifest.fingerprint,
+        Some(FingerprintSpec {
+            kind: FingerprintKind::Morgan,
+            radius: Some(2),
+            num_bits: 2048,
+        })
+    );
+}
@@ -0,0 +1,44 @@
+use cheminee_similarity_model::metric::DistanceMetric;
+
+#[test]
+fn test_euclidean_distance_is_root_mean_square() {
+    let distance = DistanceMetric::Euclidean.distance(&[0.0, 0.0, 0.0, 0.0], &[1.0, 1.0, 1.0, 1.0]);
+    assert!((distance - 1.0).abs() < 1e-6);
+
+    assert_eq!(DistanceMetric::Euclidean.distance(&[0.3, -1.2], &[0.3, -1.2]), 0.0);
+}
+
+#[test]
+fn test_nearest_candidate() {
+    let candidates = [0.0, 0.0, 1.0, 1.0, 5.0, 5.0];
+    let nearest = DistanceMetric::Euclidean.nearest(candidates.chunks(2), &[0.9, 1.2]);
+
+    assert_eq!(nearest.map(|(idx, _)| idx), Some(1));
+}
+
+#[test]
+fn test_rank_returns_nearest_centroids_per_row() {
+    let centroids = [0.0, 0.0, 1.0, 1.0, 5.0, 5.0];
+    let latents = [0.9, 1.2, 4.0, 4.0];
+    let ranked = DistanceMetric::Euclidean.rank(&latents, &centroids, 2, Some(2));
+
+    let labels = ranked
+        .iter()
+        .map(|row| row.iter().map(|(label, _)| *label).collect::<Vec<i32>>())
+        .collect::<Vec<Vec<i32>>>();
+    assert_eq!(labels, vec![vec![1, 0], vec![2, 1]]);
+}
+
+#[test]
+fn test_cosine_distance_ignores_magnitude() {
+    assert!(DistanceMetric::Cosine.distance(&[1.0, 2.0], &[2.0, 4.0]).abs() < 1e-6);
+    assert!((DistanceMetric::Cosine.distance(&[1.0, 0.0], &[0.0, 3.0]) - 1.0).abs() < 1e-6);
+    assert!((DistanceMetric::Cosine.distance(&[1.0, 0.0], &[-1.0, 0.0]) - 2.0).abs() < 1e-6);
+    assert_eq!(DistanceMetric::Cosine.distance(&[0.0, 0.0], &[1.0, 1.0]), 1.0);
+}
+
+#[test]
+fn test_manhattan_distance_is_mean_absolute_difference() {
+    let distance = DistanceMetric::Manhattan.distance(&[0.0, 0.0, 0.0, 0.0], &[1.0, -1.0, 2.0, 0.0]);
+    assert!((distance - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,25 @@
+use cheminee_similarity_model::npy::NpyWriter;
+
+#[test]
+fn test_npy_writer_patches_row_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("latents.npy");
+    let path = path.to_str().unwrap();
+
+    let mut writer = NpyWriter::create(path, 2).unwrap();
+    writer.write_rows(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+    writer.write_rows(&[5.0, 6.0]).unwrap();
+    assert!(writer.write_rows(&[7.0]).is_err());
+    assert_eq!(writer.finish().unwrap(), 3);
+
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..6], b"\x93NUMPY");
+    assert_eq!(bytes.len(), 128 + 6 * 4);
+    assert_eq!(bytes[127], b'\n');
+
+    let header = String::from_utf8_lossy(&bytes[10..128]);
+    assert!(header.contains("'shape': (3, 2)"));
+
+    let last = f32::from_le_bytes(bytes[bytes.len() - 4..].try_into().unwrap());
+    assert_eq!(last, 6.0);
+}
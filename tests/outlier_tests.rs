@@ -0,0 +1,45 @@
+use cheminee_similarity_model::outlier::{outlier_scores, reconstruction_error, OutlierThresholds};
+
+#[test]
+fn test_outlier_thresholds() {
+    let scores = outlier_scores(&[(3, 0.5), (7, 0.52), (1, 0.9)]).unwrap();
+    assert!((scores.margin - 0.02).abs() < 1e-6);
+
+    let thresholds = OutlierThresholds {
+        max_nearest_distance: Some(1.0),
+        min_margin: None,
+        max_reconstruction_error: None,
+    };
+    assert!(!thresholds.is_outlier(&scores));
+
+    let thresholds = OutlierThresholds {
+        max_nearest_distance: Some(1.0),
+        min_margin: Some(0.05),
+        max_reconstruction_error: None,
+    };
+    assert!(thresholds.is_outlier(&scores));
+}
+
+#[test]
+fn test_reconstruction_error_threshold() {
+    let mut scores = outlier_scores(&[(3, 0.5), (7, 0.9)]).unwrap();
+    let thresholds = OutlierThresholds {
+        max_reconstruction_error: Some(0.5),
+        ..OutlierThresholds::default()
+    };
+
+    // Without a decoder score the threshold has nothing to check
+    assert!(!thresholds.is_outlier(&scores));
+
+    scores.reconstruction_error = Some(reconstruction_error(&[1, 0, 1, 0], &[0.9, 0.1, 0.8, 0.2]).unwrap());
+    assert!(!thresholds.is_outlier(&scores));
+
+    scores.reconstruction_error = Some(reconstruction_error(&[1, 0, 1, 0], &[0.1, 0.9, 0.2, 0.8]).unwrap());
+    assert!(thresholds.is_outlier(&scores));
+}
+
+#[test]
+fn test_reconstruction_error_rejects_mismatched_widths() {
+    assert!(reconstruction_error(&[1, 0], &[0.5]).is_err());
+    assert!(reconstruction_error(&[], &[]).is_err());
+}
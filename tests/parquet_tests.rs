@@ -0,0 +1,135 @@
+#![cfg(feature = "parquet")]
+
+use arrow::array::{ArrayRef, AsArray, FixedSizeListBuilder, Int32Array, Int64Builder};
+use arrow::datatypes::{Field, Float32Type, Int32Type, Schema};
+use arrow::record_batch::RecordBatch;
+use cheminee_similarity_model::config::{EncoderConfig, NanPolicy};
+use cheminee_similarity_model::encoder::{build_encoder_model, build_encoder_model_with_config};
+use cheminee_similarity_model::parquet_io::ParquetTransformOptions;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+fn fingerprints() -> Vec<Vec<i64>> {
+    let mut fingerprints = vec![vec![0; 2048]; 3];
+    for bit in [1, 11, 41, 80, 1023] {
+        fingerprints[1][bit] = 1;
+    }
+    fingerprints[2].fill(1);
+    fingerprints
+}
+
+// Writes `fingerprints` plus an `id` column that should pass through untouched
+fn write_input(path: &Path) {
+    let mut column = FixedSizeListBuilder::new(Int64Builder::new(), 2048);
+    for row in fingerprints() {
+        column.values().append_slice(&row);
+        column.append(true);
+    }
+    let column: ArrayRef = Arc::new(column.finish());
+    let ids: ArrayRef = Arc::new(Int32Array::from(vec![10, 11, 12]));
+
+    let schema = Schema::new(vec![
+        Field::new("id", ids.data_type().clone(), false),
+        Field::new("fingerprint", column.data_type().clone(), true),
+    ]);
+    let batch = RecordBatch::try_new(Arc::new(schema), vec![ids, column]).unwrap();
+
+    let mut writer = ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+fn read_output(path: &Path) -> Vec<RecordBatch> {
+    ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+        .unwrap()
+        .build()
+        .unwrap()
+        .collect::<Result<Vec<RecordBatch>, _>>()
+        .unwrap()
+}
+
+#[test]
+fn test_transform_parquet_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input_path = dir.path().join("input.parquet");
+    let output_path = dir.path().join("output.parquet");
+    write_input(&input_path);
+
+    let encoder_model = build_encoder_model().unwrap();
+    let options = ParquetTransformOptions {
+        top_k: 3,
+        batch_size: 2,
+        write_latents: true,
+        ..ParquetTransformOptions::default()
+    };
+    let rows_written = encoder_model
+        .transform_parquet(input_path.to_str().unwrap(), output_path.to_str().unwrap(), &options)
+        .unwrap();
+    assert_eq!(rows_written, 3);
+
+    let expected_labels = encoder_model.transform_top_n(&fingerprints(), 3).unwrap();
+    let expected_latents = encoder_model.encode_latent(&fingerprints()).unwrap();
+
+    let batches = read_output(&output_path);
+    let mut row_idx = 0;
+    for batch in &batches {
+        assert!(batch.column_by_name("fingerprint").is_none());
+        let ids = batch.column_by_name("id").unwrap().as_primitive::<Int32Type>();
+        let labels = batch.column_by_name("cluster_labels").unwrap().as_fixed_size_list();
+        let distances = batch.column_by_name("cluster_distances").unwrap().as_fixed_size_list();
+        let latents = batch.column_by_name("latent").unwrap().as_fixed_size_list();
+
+        for batch_row in 0..batch.num_rows() {
+            assert_eq!(ids.value(batch_row), 10 + row_idx as i32);
+
+            let row_labels = labels.value(batch_row);
+            assert_eq!(row_labels.as_primitive::<Int32Type>().values().to_vec(), expected_labels[row_idx]);
+
+            let row_distances = distances.value(batch_row);
+            let row_distances = row_distances.as_primitive::<Float32Type>().values();
+            assert!(row_distances.windows(2).all(|pair| pair[0] <= pair[1]));
+
+            let row_latent = latents.value(batch_row);
+            let row_latent = row_latent.as_primitive::<Float32Type>().values();
+            let expected_latent = expected_latents.row(row_idx);
+            assert!(row_latent.iter().zip(expected_latent).all(|(a, b)| (a - b).abs() < 1e-4));
+
+            row_idx += 1;
+        }
+    }
+    assert_eq!(row_idx, 3);
+}
+
+// Rows ranked with fewer than `top_k` clusters, which is what NaN-skipped rows come back as,
+// are written as null rather than padded
+#[test]
+fn test_transform_parquet_writes_null_for_unranked_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    let input_path = dir.path().join("input.parquet");
+    let output_path = dir.path().join("output.parquet");
+    write_input(&input_path);
+
+    let config = EncoderConfig {
+        nan_policy: NanPolicy::SkipRow,
+        ..EncoderConfig::default()
+    };
+    let encoder_model = build_encoder_model_with_config(config).unwrap();
+    let options = ParquetTransformOptions {
+        top_k: encoder_model.num_clusters() + 1,
+        ..ParquetTransformOptions::default()
+    };
+    let rows_written = encoder_model
+        .transform_parquet(input_path.to_str().unwrap(), output_path.to_str().unwrap(), &options)
+        .unwrap();
+    assert_eq!(rows_written, 3);
+
+    for batch in read_output(&output_path) {
+        let labels = batch.column_by_name("cluster_labels").unwrap();
+        let distances = batch.column_by_name("cluster_distances").unwrap();
+        assert_eq!(labels.null_count(), batch.num_rows());
+        assert_eq!(distances.null_count(), batch.num_rows());
+    }
+}
@@ -0,0 +1,25 @@
+use cheminee_similarity_model::config::EncoderConfig;
+use cheminee_similarity_model::encoder::build_encoder_model;
+use cheminee_similarity_model::pool::EncoderPool;
+
+#[test]
+fn test_pool_round_robins_models() {
+    let pool = EncoderPool::new(2, EncoderConfig::default()).unwrap();
+    assert_eq!(pool.size(), 2);
+
+    let first = pool.get() as *const _;
+    let second = pool.get() as *const _;
+    assert_ne!(first, second);
+    assert_eq!(first, pool.get() as *const _);
+
+    let input_data = vec![vec![0; 2048]];
+    assert_eq!(
+        pool.transform(&input_data).unwrap(),
+        build_encoder_model().unwrap().transform(&input_data).unwrap()
+    );
+}
+
+#[test]
+fn test_empty_pool_is_rejected() {
+    assert!(EncoderPool::new(0, EncoderConfig::default()).is_err());
+}
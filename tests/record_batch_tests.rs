@@ -0,0 +1,87 @@
+#![cfg(feature = "arrow")]
+
+use arrow::array::{ArrayRef, AsArray, BooleanBuilder, FixedSizeListBuilder, Int64Builder, ListBuilder};
+use arrow::datatypes::{Field, Int32Type, Schema};
+use arrow::record_batch::RecordBatch;
+use cheminee_similarity_model::encoder::{build_encoder_model, EncoderModel};
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::record_batch::LABEL_COLUMN;
+use std::sync::Arc;
+
+fn fingerprints() -> Vec<Vec<i64>> {
+    let mut fingerprints = vec![vec![0; 2048], vec![0; 2048]];
+    for bit in [1, 11, 41, 80, 1023] {
+        fingerprints[1][bit] = 1;
+    }
+    fingerprints
+}
+
+fn batch(column: ArrayRef) -> RecordBatch {
+    let schema = Schema::new(vec![Field::new("fingerprint", column.data_type().clone(), true)]);
+    RecordBatch::try_new(Arc::new(schema), vec![column]).unwrap()
+}
+
+fn assert_labels_match(encoder_model: &EncoderModel, batch: &RecordBatch) {
+    let transformed = encoder_model.transform_arrow(batch).unwrap();
+    let labels = transformed.column_by_name(LABEL_COLUMN).unwrap().as_primitive::<Int32Type>();
+
+    let expected = encoder_model.transform_top_n(&fingerprints(), 1).unwrap();
+    assert_eq!(transformed.num_rows(), expected.len());
+    for (row_idx, row) in expected.iter().enumerate() {
+        assert_eq!(labels.value(row_idx), row[0]);
+    }
+}
+
+#[test]
+fn test_transform_arrow_fixed_size_list_column() {
+    let mut column = FixedSizeListBuilder::new(Int64Builder::new(), 2048);
+    for row in fingerprints() {
+        column.values().append_slice(&row);
+        column.append(true);
+    }
+
+    assert_labels_match(&build_encoder_model().unwrap(), &batch(Arc::new(column.finish())));
+}
+
+#[test]
+fn test_transform_arrow_list_column() {
+    let mut column = ListBuilder::new(Int64Builder::new());
+    for row in fingerprints() {
+        column.values().append_slice(&row);
+        column.append(true);
+    }
+
+    assert_labels_match(&build_encoder_model().unwrap(), &batch(Arc::new(column.finish())));
+}
+
+#[test]
+fn test_transform_arrow_boolean_column() {
+    let mut column = FixedSizeListBuilder::new(BooleanBuilder::new(), 2048);
+    for row in fingerprints() {
+        for bit in row {
+            column.values().append_value(bit != 0);
+        }
+        column.append(true);
+    }
+
+    assert_labels_match(&build_encoder_model().unwrap(), &batch(Arc::new(column.finish())));
+}
+
+#[test]
+fn test_transform_arrow_rejects_null_rows_and_bits() {
+    let encoder_model = build_encoder_model().unwrap();
+
+    let mut column = ListBuilder::new(Int64Builder::new());
+    column.values().append_slice(&fingerprints()[0]);
+    column.append(true);
+    column.append(false);
+    let result = encoder_model.transform_arrow(&batch(Arc::new(column.finish())));
+    assert!(matches!(result, Err(EncoderError::ShapeMismatch(_))));
+
+    let mut column = ListBuilder::new(Int64Builder::new());
+    column.values().append_slice(&fingerprints()[0][1..]);
+    column.values().append_null();
+    column.append(true);
+    let result = encoder_model.transform_arrow(&batch(Arc::new(column.finish())));
+    assert!(matches!(result, Err(EncoderError::ShapeMismatch(_))));
+}
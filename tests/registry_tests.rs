@@ -0,0 +1,19 @@
+use cheminee_similarity_model::encoder::{build_encoder_model, get_assets_path};
+use cheminee_similarity_model::registry::ModelRegistry;
+use std::sync::Arc;
+
+#[test]
+fn test_reload_keeps_in_flight_snapshot() {
+    let registry = ModelRegistry::new(build_encoder_model().unwrap());
+    let before = registry.current();
+
+    let replaced = registry.reload(&get_assets_path().unwrap()).unwrap();
+    assert!(Arc::ptr_eq(&before, &replaced));
+    assert!(!Arc::ptr_eq(&before, &registry.current()));
+
+    let input_data = vec![vec![0; 2048]];
+    assert_eq!(
+        before.transform(&input_data).unwrap(),
+        registry.current().transform(&input_data).unwrap()
+    );
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "server")]
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use cheminee_similarity_model::encoder::{build_encoder_model, EncoderModel};
+use cheminee_similarity_model::server::{router, ServerConfig};
+use cheminee_similarity_model::stats::ClusterAssignment;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+#[derive(Deserialize)]
+struct EncodeReply {
+    latents: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct AssignReply {
+    assignments: Vec<ClusterAssignment>,
+}
+
+fn model() -> Arc<EncoderModel> {
+    Arc::new(build_encoder_model().unwrap())
+}
+
+// A batch delay long enough that requests sent together always land in the same batch
+fn merging_config() -> ServerConfig {
+    ServerConfig {
+        max_batch_delay: Duration::from_millis(200),
+        ..ServerConfig::default()
+    }
+}
+
+// Rows can encode slightly differently depending on which batch they share
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    assert!(actual.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-4));
+}
+
+fn labels(assignments: &[ClusterAssignment]) -> Vec<Vec<i32>> {
+    assignments.iter().map(|assignment| assignment.labels.clone()).collect()
+}
+
+async fn post(app: Router, uri: &str, body: serde_json::Value) -> (StatusCode, Vec<u8>) {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, bytes.to_vec())
+}
+
+#[tokio::test]
+async fn test_encode_endpoint() {
+    let model = model();
+    let app = router(model.clone(), ServerConfig::default());
+    let fingerprints = vec![vec![0; 2048], vec![1; 2048]];
+
+    let (status, body) = post(app, "/encode", json!({ "fingerprints": fingerprints })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let reply: EncodeReply = serde_json::from_slice(&body).unwrap();
+    let expected = model.encode_latent(&fingerprints).unwrap();
+    assert_eq!(reply.latents.len(), 2);
+    assert_close(&reply.latents[0], &expected.row(0).to_vec());
+}
+
+#[tokio::test]
+async fn test_assign_endpoint() {
+    let model = model();
+    let app = router(model.clone(), ServerConfig::default());
+    let fingerprints = vec![vec![0; 2048]];
+
+    let (status, body) = post(app.clone(), "/assign", json!({ "fingerprints": fingerprints, "top_n": 3 })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let reply: AssignReply = serde_json::from_slice(&body).unwrap();
+    assert_eq!(labels(&reply.assignments), labels(&model.transform_assignments(&fingerprints, 3).unwrap()));
+
+    let (status, _) = post(app, "/assign", json!({ "fingerprints": fingerprints, "top_n": 0 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_merged_requests_keep_their_own_rows_and_top_n() {
+    let model = model();
+    let app = router(model.clone(), merging_config());
+    let first = vec![vec![0; 2048]];
+    let second = vec![vec![1; 2048], vec![0; 2048]];
+
+    let ((first_status, first_body), (second_status, second_body)) = tokio::join!(
+        post(app.clone(), "/assign", json!({ "fingerprints": first, "top_n": 1 })),
+        post(app.clone(), "/assign", json!({ "fingerprints": second, "top_n": 4 })),
+    );
+    assert_eq!(first_status, StatusCode::OK);
+    assert_eq!(second_status, StatusCode::OK);
+
+    let first_reply: AssignReply = serde_json::from_slice(&first_body).unwrap();
+    let second_reply: AssignReply = serde_json::from_slice(&second_body).unwrap();
+    assert_eq!(labels(&first_reply.assignments), labels(&model.transform_assignments(&first, 1).unwrap()));
+    assert_eq!(labels(&second_reply.assignments), labels(&model.transform_assignments(&second, 4).unwrap()));
+}
+
+#[tokio::test]
+async fn test_failed_merged_batch_reruns_each_request() {
+    let model = model();
+    let app = router(model.clone(), merging_config());
+    let valid = vec![vec![0; 2048]];
+    let malformed = vec![vec![0; 16]];
+
+    let ((valid_status, valid_body), (malformed_status, _)) = tokio::join!(
+        post(app.clone(), "/encode", json!({ "fingerprints": valid })),
+        post(app.clone(), "/encode", json!({ "fingerprints": malformed })),
+    );
+    assert_eq!(valid_status, StatusCode::OK);
+    assert_eq!(malformed_status, StatusCode::BAD_REQUEST);
+
+    let reply: EncodeReply = serde_json::from_slice(&valid_body).unwrap();
+    assert_close(&reply.latents[0], &model.encode_latent(&valid).unwrap().row(0).to_vec());
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "tensorflow")]
+
+use cheminee_similarity_model::config::SessionConfig;
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::session::config_proto;
+
+#[test]
+fn test_default_session_config_is_empty() {
+    assert!(config_proto(&SessionConfig::default(), false).unwrap().is_empty());
+}
+
+#[test]
+fn test_thread_counts() {
+    let session = SessionConfig {
+        intra_op_threads: Some(4),
+        inter_op_threads: Some(300),
+        ..SessionConfig::default()
+    };
+
+    // intra_op_parallelism_threads = 4, inter_op_parallelism_threads = 300
+    assert_eq!(config_proto(&session, false).unwrap(), vec![0x10, 0x04, 0x28, 0xac, 0x02]);
+}
+
+#[test]
+fn test_allow_growth() {
+    let session = SessionConfig {
+        allow_growth: true,
+        ..SessionConfig::default()
+    };
+
+    // gpu_options { allow_growth: true }
+    assert_eq!(config_proto(&session, false).unwrap(), vec![0x32, 0x02, 0x20, 0x01]);
+}
+
+#[test]
+fn test_gpu_memory_fraction() {
+    let session = SessionConfig {
+        gpu_memory_fraction: Some(0.5),
+        allow_growth: true,
+        ..SessionConfig::default()
+    };
+
+    // gpu_options { per_process_gpu_memory_fraction: 0.5, allow_growth: true }
+    assert_eq!(
+        config_proto(&session, false).unwrap(),
+        vec![0x32, 0x0b, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe0, 0x3f, 0x20, 0x01]
+    );
+}
+
+#[test]
+fn test_disable_gpu() {
+    let session = SessionConfig {
+        disable_gpu: true,
+        ..SessionConfig::default()
+    };
+
+    // device_count { key: "GPU" value: 0 }
+    assert_eq!(
+        config_proto(&session, false).unwrap(),
+        vec![0x0a, 0x07, 0x0a, 0x03, b'G', b'P', b'U', 0x10, 0x00]
+    );
+}
+
+#[test]
+fn test_rejects_out_of_range_gpu_memory_fraction() {
+    for fraction in [0.0, 1.5, f64::NAN] {
+        let session = SessionConfig {
+            gpu_memory_fraction: Some(fraction),
+            ..SessionConfig::default()
+        };
+
+        assert!(matches!(config_proto(&session, false), Err(EncoderError::InvalidArgument(_))));
+    }
+}
+
+#[test]
+fn test_bfloat16_rewrite() {
+    // graph_options { rewrite_options { auto_mixed_precision_onednn_bfloat16: ON } }
+    assert_eq!(
+        config_proto(&SessionConfig::default(), true).unwrap(),
+        vec![0x52, 0x05, 0x52, 0x03, 0xf8, 0x01, 0x01]
+    );
+
+    let session = SessionConfig {
+        intra_op_threads: Some(2),
+        ..SessionConfig::default()
+    };
+    assert_eq!(
+        config_proto(&session, true).unwrap(),
+        vec![0x10, 0x02, 0x52, 0x05, 0x52, 0x03, 0xf8, 0x01, 0x01]
+    );
+}
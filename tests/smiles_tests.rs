@@ -0,0 +1,26 @@
+#![cfg(feature = "rdkit")]
+
+use cheminee_similarity_model::encoder::build_encoder_model;
+use cheminee_similarity_model::error::EncoderError;
+use cheminee_similarity_model::smiles::{smiles_fingerprint, FINGERPRINT_BITS};
+
+#[test]
+fn test_smiles_fingerprint() {
+    let fingerprint = smiles_fingerprint("c1ccccc1O").unwrap();
+    assert_eq!(fingerprint.len(), FINGERPRINT_BITS);
+    assert!(fingerprint.iter().all(|bit| *bit == 0 || *bit == 1));
+    assert!(fingerprint.contains(&1));
+}
+
+#[test]
+fn test_transform_smiles() {
+    let model = build_encoder_model().unwrap();
+    let smiles = ["CCO", "c1ccccc1O"];
+
+    let labels = model.transform_smiles(&smiles).unwrap();
+    let fingerprints = smiles.iter().map(|smiles| smiles_fingerprint(smiles).unwrap()).collect::<Vec<_>>();
+    assert_eq!(labels, model.transform(&fingerprints).unwrap());
+
+    let result = model.transform_smiles(&["CCO", "not a smiles"]);
+    assert!(matches!(result, Err(EncoderError::InvalidArgument(message)) if message.starts_with("Row 1")));
+}
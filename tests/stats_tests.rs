@@ -0,0 +1,63 @@
+use cheminee_similarity_model::stats::{
+    jensen_shannon_divergence, normalized_entropy, percentile_ranks, recall_threshold,
+    softmax_over_distances, summarize_distances, ClusterAssignment,
+};
+
+#[test]
+fn test_summarize_distances_skips_unassigned_rows() {
+    let distances = (1..=20).map(|i| i as f32).chain([f32::NAN]).collect::<Vec<f32>>();
+    let summary = summarize_distances(&distances).unwrap();
+
+    assert!((summary.mean - 10.5).abs() < 1e-6);
+    assert_eq!(summary.max, 20.0);
+    assert!((summary.p95 - 19.05).abs() < 1e-4);
+}
+
+#[test]
+fn test_normalized_entropy() {
+    assert!((normalized_entropy(&[5, 5, 5, 5], 4) - 1.0).abs() < 1e-6);
+    assert_eq!(normalized_entropy(&[20], 4), 0.0);
+    assert!(normalized_entropy(&[10, 10], 4) < 1.0);
+}
+
+#[test]
+fn test_recall_threshold() {
+    let distances = [0.4, 0.1, 0.3, f32::NAN, 0.2];
+
+    assert_eq!(recall_threshold(&distances, 0.4).unwrap(), 0.2);
+    assert_eq!(recall_threshold(&distances, 0.8).unwrap(), 0.4);
+    assert!(recall_threshold(&distances, 1.0).is_err());
+}
+
+#[test]
+fn test_jensen_shannon_divergence() {
+    let p = softmax_over_distances(&[0.1, 0.5, 0.9], 0.2);
+    assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!(p[0] > p[1] && p[1] > p[2]);
+
+    assert!(jensen_shannon_divergence(&p, &p).abs() < 1e-7);
+
+    let disjoint = jensen_shannon_divergence(&[1.0, 0.0], &[0.0, 1.0]);
+    assert!((disjoint - std::f32::consts::LN_2).abs() < 1e-6);
+}
+
+#[test]
+fn test_percentile_ranks() {
+    let ranks = percentile_ranks(&[0.3, 0.1, f32::NAN, 0.4, 0.1]);
+
+    assert_eq!(ranks[0], 0.75);
+    assert_eq!(ranks[1], 0.5);
+    assert!(ranks[2].is_nan());
+    assert_eq!(ranks[3], 1.0);
+}
+
+#[test]
+fn test_cluster_assignment_round_trips_through_json() {
+    let assignment = ClusterAssignment::from(vec![(7, 0.25), (3, 0.5)]);
+    assert_eq!(assignment.labels, vec![7, 3]);
+    assert_eq!(assignment.distances, vec![0.25, 0.5]);
+
+    let json = serde_json::to_string(&assignment).unwrap();
+    assert_eq!(json, r#"{"labels":[7,3],"distances":[0.25,0.5]}"#);
+    assert_eq!(serde_json::from_str::<ClusterAssignment>(&json).unwrap(), assignment);
+}
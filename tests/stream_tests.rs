@@ -0,0 +1,30 @@
+#![cfg(feature = "tokio")]
+
+use cheminee_similarity_model::encoder::build_encoder_model;
+use futures::stream::{self, StreamExt};
+use std::sync::Arc;
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_transform_stream_on_current_thread_runtime() {
+    let encoder_model = Arc::new(build_encoder_model().unwrap());
+    let input_data = vec![vec![0; 2048], vec![1; 2048], vec![0; 2048]];
+    let expected = encoder_model.transform(&input_data).unwrap();
+
+    let rows = Arc::clone(&encoder_model)
+        .transform_stream(stream::iter(input_data), 2)
+        .collect::<Vec<_>>()
+        .await;
+
+    let rows = rows.into_iter().collect::<Result<Vec<Vec<i32>>, _>>().unwrap();
+    assert_eq!(rows, expected);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_transform_stream_yields_batch_errors() {
+    let encoder_model = Arc::new(build_encoder_model().unwrap());
+    let input_data = vec![vec![0; 2048], vec![0; 16]];
+
+    let rows = encoder_model.transform_stream(stream::iter(input_data), 2).collect::<Vec<_>>().await;
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0].is_err());
+}